@@ -0,0 +1,140 @@
+// Filesystem micro-benchmarks, run with `--bench`.
+// Each benchmark returns named metrics, which are reported like test results.
+
+use std::fs;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::time::Instant;
+
+use crate::assert_eq_res;
+
+pub type Metrics = Vec<(&'static str, f64)>;
+pub type Benchmark = Box<dyn Fn() -> Result<Metrics, Box<dyn std::error::Error>>>;
+
+const BUFFER_SIZES: [usize; 4] = [512, 4096, 64 * 1024, 1024 * 1024];
+
+// Rate of creating and deleting small files in one directory
+fn bench_create_delete_files() -> Result<Metrics, Box<dyn std::error::Error>> {
+    let dir_path = "bench_create_delete";
+    let file_count = 1000 * crate::scale() as usize;
+    fs::create_dir(dir_path)?;
+
+    let start = Instant::now();
+    for i in 0..file_count {
+        fs::write(format!("{dir_path}/file_{i}.txt"), "small file")?;
+    }
+    let create_secs = start.elapsed().as_secs_f64();
+
+    let start = Instant::now();
+    for i in 0..file_count {
+        fs::remove_file(format!("{dir_path}/file_{i}.txt"))?;
+    }
+    let delete_secs = start.elapsed().as_secs_f64();
+
+    fs::remove_dir(dir_path)?;
+    Ok(vec![
+        ("files", file_count as f64),
+        ("creates_per_sec", file_count as f64 / create_secs),
+        ("deletes_per_sec", file_count as f64 / delete_secs),
+    ])
+}
+
+// Sequential write and read throughput through BufWriter/BufReader of the given capacity
+fn bench_sequential_io(buffer_size: usize) -> Result<Metrics, Box<dyn std::error::Error>> {
+    const RECORD_SIZE: usize = 100;
+    let file_path = format!("bench_sequential_{buffer_size}.bin");
+    let total_size = 16 * 1024 * 1024 * crate::scale() as usize;
+    let record = [0xa5; RECORD_SIZE];
+
+    let start = Instant::now();
+    let mut writer = BufWriter::with_capacity(buffer_size, fs::File::create(&file_path)?);
+    for _ in 0..total_size / RECORD_SIZE {
+        writer.write_all(&record)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    let write_secs = start.elapsed().as_secs_f64();
+
+    let start = Instant::now();
+    let mut reader = BufReader::with_capacity(buffer_size, fs::File::open(&file_path)?);
+    let mut buf = [0; RECORD_SIZE];
+    let mut read_size = 0;
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        read_size += n;
+    }
+    let read_secs = start.elapsed().as_secs_f64();
+
+    let written_size = total_size / RECORD_SIZE * RECORD_SIZE;
+    assert_eq_res!(read_size, written_size);
+    fs::remove_file(&file_path)?;
+    let mib = written_size as f64 / (1024.0 * 1024.0);
+    Ok(vec![
+        ("buffer_size", buffer_size as f64),
+        ("write_mib_per_sec", mib / write_secs),
+        ("read_mib_per_sec", mib / read_secs),
+    ])
+}
+
+// Listing speed of a directory with many entries
+fn bench_read_dir() -> Result<Metrics, Box<dyn std::error::Error>> {
+    const ROUNDS: usize = 10;
+    let dir_path = "bench_read_dir";
+    let entry_count = 5000 * crate::scale() as usize;
+    fs::create_dir(dir_path)?;
+    for i in 0..entry_count {
+        fs::File::create(format!("{dir_path}/entry_{i}"))?;
+    }
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let listed = fs::read_dir(dir_path)?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq_res!(listed.len(), entry_count);
+    }
+    let secs = start.elapsed().as_secs_f64();
+
+    fs::remove_dir_all(dir_path)?;
+    Ok(vec![
+        ("entries", entry_count as f64),
+        ("entries_per_sec", (entry_count * ROUNDS) as f64 / secs),
+    ])
+}
+
+// Latency of metadata calls on an existing file
+fn bench_metadata() -> Result<Metrics, Box<dyn std::error::Error>> {
+    let file_path = "bench_metadata.txt";
+    let call_count = 10_000 * crate::scale() as usize;
+    fs::write(file_path, "metadata")?;
+
+    let start = Instant::now();
+    for _ in 0..call_count {
+        std::hint::black_box(fs::metadata(file_path)?);
+    }
+    let secs = start.elapsed().as_secs_f64();
+
+    fs::remove_file(file_path)?;
+    Ok(vec![
+        ("calls", call_count as f64),
+        ("latency_us", secs * 1e6 / call_count as f64),
+    ])
+}
+
+pub fn benchmarks() -> Vec<(String, Benchmark)> {
+    let mut benchmarks: Vec<(String, Benchmark)> = vec![(
+        "bench::create_delete_files".to_string(),
+        Box::new(bench_create_delete_files),
+    )];
+    for buffer_size in BUFFER_SIZES {
+        benchmarks.push((
+            format!("bench::sequential_io_{buffer_size}"),
+            Box::new(move || bench_sequential_io(buffer_size)),
+        ));
+    }
+    benchmarks.push(("bench::read_dir".to_string(), Box::new(bench_read_dir)));
+    benchmarks.push(("bench::metadata".to_string(), Box::new(bench_metadata)));
+    benchmarks
+}
@@ -0,0 +1,337 @@
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs::{self, TryLockError};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Barrier, mpsc};
+use std::thread;
+use std::time::Duration;
+
+use crate::{Skipped, assert_eq_res, assert_res};
+
+const THREAD_COUNT: usize = 10;
+const ITER_COUNT: usize = 100;
+
+// Test many threads appending records to the same file through their own handles
+pub fn test_concurrent_append() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "concurrent_append.txt";
+    fs::File::create(file_path)?;
+    let barrier = Arc::new(Barrier::new(THREAD_COUNT));
+    let mut handles = vec![];
+
+    for t in 0..THREAD_COUNT {
+        let barrier = Arc::clone(&barrier);
+        let handle = thread::spawn(move || {
+            let mut file = fs::OpenOptions::new()
+                .append(true)
+                .open(file_path)
+                .map_err(|e| e.to_string())?;
+            barrier.wait();
+            for r in 0..ITER_COUNT {
+                // Every record is written by a single call, so it must not be split
+                file.write_all(format!("thread {t:02} record {r:04}\n").as_bytes())
+                    .map_err(|e| e.to_string())?;
+            }
+            Ok::<_, String>(())
+        });
+        handles.push(handle);
+    }
+
+    for handle in handles {
+        handle.join().map_err(|_| "Thread panicked".to_string())??;
+    }
+
+    let content = fs::read_to_string(file_path)?;
+    let mut next_record = [0; THREAD_COUNT];
+    for line in content.lines() {
+        let parsed = line
+            .strip_prefix("thread ")
+            .and_then(|rest| rest.split_once(" record "))
+            .and_then(|(t, r)| Some((t.parse::<usize>().ok()?, r.parse::<usize>().ok()?)));
+        let Some((t, r)) = parsed.filter(|&(t, _)| t < THREAD_COUNT) else {
+            return Err(format!("Malformed record: {line:?}").into());
+        };
+        // Records of a single thread keep their order
+        assert_eq_res!(r, next_record[t]);
+        next_record[t] += 1;
+    }
+    assert_eq_res!(next_record, [ITER_COUNT; THREAD_COUNT]);
+    assert_eq_res!(content.lines().count(), THREAD_COUNT * ITER_COUNT);
+    Ok(())
+}
+
+// Test threads creating and removing distinct files in one directory
+pub fn test_concurrent_create_remove() -> Result<(), Box<dyn std::error::Error>> {
+    let dir_path = "concurrent_create_remove";
+    fs::create_dir(dir_path)?;
+    let mut handles = vec![];
+
+    for t in 0..THREAD_COUNT {
+        let handle = thread::spawn(move || {
+            for r in 0..ITER_COUNT {
+                let file_path = format!("{dir_path}/thread_{t}_file_{r}.txt");
+                fs::write(&file_path, format!("{t} {r}")).map_err(|e| e.to_string())?;
+                let content = fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
+                assert_eq_res!(content, format!("{t} {r}"));
+                // Keep every tenth file, remove the rest
+                if r % 10 != 0 {
+                    fs::remove_file(&file_path).map_err(|e| e.to_string())?;
+                }
+            }
+            Ok::<_, String>(())
+        });
+        handles.push(handle);
+    }
+
+    for handle in handles {
+        handle.join().map_err(|_| "Thread panicked".to_string())??;
+    }
+
+    let listed: HashSet<String> = fs::read_dir(dir_path)?
+        .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().into_owned()))
+        .collect::<Result<_, _>>()?;
+    let expected: HashSet<String> = (0..THREAD_COUNT)
+        .flat_map(|t| (0..ITER_COUNT).step_by(10).map(move |r| (t, r)))
+        .map(|(t, r)| format!("thread_{t}_file_{r}.txt"))
+        .collect();
+    assert_eq_res!(listed, expected);
+    Ok(())
+}
+
+// Test listing a directory while other threads create files in it
+pub fn test_concurrent_read_dir() -> Result<(), Box<dyn std::error::Error>> {
+    let dir_path = "concurrent_read_dir";
+    let initial_count = 50;
+    fs::create_dir(dir_path)?;
+    for i in 0..initial_count {
+        fs::write(format!("{dir_path}/initial_{i}.txt"), "initial")?;
+    }
+
+    let writer_count = THREAD_COUNT / 2;
+    let reader_count = THREAD_COUNT - writer_count;
+    let barrier = Arc::new(Barrier::new(THREAD_COUNT));
+    let mut writers = vec![];
+    let mut readers = vec![];
+
+    for t in 0..writer_count {
+        let barrier = Arc::clone(&barrier);
+        writers.push(thread::spawn(move || {
+            barrier.wait();
+            for r in 0..ITER_COUNT {
+                fs::write(format!("{dir_path}/new_{t}_{r}.txt"), "new")
+                    .map_err(|e| e.to_string())?;
+            }
+            Ok::<_, String>(())
+        }));
+    }
+
+    for _ in 0..reader_count {
+        let barrier = Arc::clone(&barrier);
+        readers.push(thread::spawn(move || {
+            barrier.wait();
+            let mut previous_count = 0;
+            for _ in 0..ITER_COUNT / 10 {
+                let mut names = HashSet::new();
+                for entry in fs::read_dir(dir_path).map_err(|e| e.to_string())? {
+                    let name = entry.map_err(|e| e.to_string())?.file_name();
+                    if !names.insert(name.clone()) {
+                        return Err(format!("Entry {name:?} listed twice"));
+                    }
+                }
+                // Files that existed before the listing started must always be returned
+                for i in 0..initial_count {
+                    assert_res!(names.contains(&OsString::from(format!("initial_{i}.txt"))));
+                }
+                // Files are only being added, so listings never shrink
+                assert_res!(names.len() >= previous_count);
+                previous_count = names.len();
+            }
+            Ok::<_, String>(())
+        }));
+    }
+
+    for handle in writers.into_iter().chain(readers) {
+        handle.join().map_err(|_| "Thread panicked".to_string())??;
+    }
+
+    let total = fs::read_dir(dir_path)?.count();
+    assert_eq_res!(total, initial_count + writer_count * ITER_COUNT);
+    Ok(())
+}
+
+// Test threads racing to rename the same files
+pub fn test_concurrent_rename() -> Result<(), Box<dyn std::error::Error>> {
+    let dir_path = "concurrent_rename";
+    fs::create_dir(dir_path)?;
+
+    // All threads try to move one source away, exactly one of them may succeed
+    fs::write(format!("{dir_path}/contested.txt"), "contested")?;
+    let barrier = Arc::new(Barrier::new(THREAD_COUNT));
+    let mut handles = vec![];
+    for t in 0..THREAD_COUNT {
+        let barrier = Arc::clone(&barrier);
+        handles.push(thread::spawn(move || {
+            barrier.wait();
+            match fs::rename(
+                format!("{dir_path}/contested.txt"),
+                format!("{dir_path}/winner_{t}.txt"),
+            ) {
+                Ok(()) => Ok(true),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+                Err(e) => Err(e.to_string()),
+            }
+        }));
+    }
+    let mut winners = 0;
+    for handle in handles {
+        if handle.join().map_err(|_| "Thread panicked".to_string())?? {
+            winners += 1;
+        }
+    }
+    assert_eq_res!(winners, 1);
+    assert_res!(!Path::new(&format!("{dir_path}/contested.txt")).exists());
+
+    // All threads replace one destination, it must end up as one complete file
+    let mut handles = vec![];
+    for t in 0..THREAD_COUNT {
+        let barrier = Arc::clone(&barrier);
+        handles.push(thread::spawn(move || {
+            for r in 0..ITER_COUNT / 10 {
+                let src_path = format!("{dir_path}/source_{t}.txt");
+                fs::write(&src_path, format!("thread {t} round {r}")).map_err(|e| e.to_string())?;
+                if r == 0 {
+                    barrier.wait();
+                }
+                fs::rename(&src_path, format!("{dir_path}/destination.txt"))
+                    .map_err(|e| e.to_string())?;
+            }
+            Ok::<_, String>(())
+        }));
+    }
+    for handle in handles {
+        handle.join().map_err(|_| "Thread panicked".to_string())??;
+    }
+
+    let content = fs::read_to_string(format!("{dir_path}/destination.txt"))?;
+    let last_round = format!("round {}", ITER_COUNT / 10 - 1);
+    assert_res!(content.starts_with("thread ") && content.ends_with(&last_round));
+    let listed = fs::read_dir(dir_path)?.count();
+    assert_eq_res!(listed, 2);
+    Ok(())
+}
+
+// Locking is optional for platforms, report the tests as skipped when it is missing
+fn lock_supported(result: io::Result<()>) -> Result<(), Box<dyn std::error::Error>> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+            Err(Skipped(format!("File locking is not supported: {e}")).into())
+        }
+        result => Ok(result?),
+    }
+}
+
+fn is_would_block(result: Result<(), TryLockError>) -> Result<bool, io::Error> {
+    match result {
+        Ok(()) => Ok(false),
+        Err(TryLockError::WouldBlock) => Ok(true),
+        Err(TryLockError::Error(e)) => Err(e),
+    }
+}
+
+fn open_lock_file(file_path: &str) -> io::Result<fs::File> {
+    fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(file_path)
+}
+
+// Test that an exclusive lock excludes all other locks on the file
+pub fn test_file_lock_exclusive() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "file_lock_exclusive.txt";
+    let first = open_lock_file(file_path)?;
+    let second = open_lock_file(file_path)?;
+
+    lock_supported(first.lock())?;
+    assert_res!(is_would_block(second.try_lock())?);
+    assert_res!(is_would_block(second.try_lock_shared())?);
+
+    first.unlock()?;
+    assert_res!(!is_would_block(second.try_lock())?);
+    assert_res!(is_would_block(first.try_lock())?);
+    second.unlock()?;
+    Ok(())
+}
+
+// Test that shared locks coexist, but exclude an exclusive lock
+pub fn test_file_lock_shared() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "file_lock_shared.txt";
+    let first = open_lock_file(file_path)?;
+    let second = open_lock_file(file_path)?;
+    let third = open_lock_file(file_path)?;
+
+    lock_supported(first.lock_shared())?;
+    assert_res!(!is_would_block(second.try_lock_shared())?);
+    assert_res!(is_would_block(third.try_lock())?);
+
+    first.unlock()?;
+    assert_res!(is_would_block(third.try_lock())?);
+    second.unlock()?;
+    assert_res!(!is_would_block(third.try_lock())?);
+    third.unlock()?;
+    Ok(())
+}
+
+// Test that closing a file releases its lock
+pub fn test_file_lock_released_on_drop() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "file_lock_drop.txt";
+    let first = open_lock_file(file_path)?;
+    let second = open_lock_file(file_path)?;
+
+    lock_supported(first.lock())?;
+    assert_res!(is_would_block(second.try_lock())?);
+    drop(first);
+    assert_res!(!is_would_block(second.try_lock())?);
+    Ok(())
+}
+
+// Test that a blocking lock in one thread waits until another thread unlocks
+pub fn test_file_lock_between_threads() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "file_lock_threads.txt";
+    let first = open_lock_file(file_path)?;
+    lock_supported(first.lock())?;
+
+    let (sender, receiver) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        let second = open_lock_file(file_path).map_err(|e| e.to_string())?;
+        second.lock().map_err(|e| e.to_string())?;
+        sender
+            .send(())
+            .map_err(|_| "Failed to send message".to_string())?;
+        Ok::<_, String>(second)
+    });
+
+    match receiver.recv_timeout(Duration::from_millis(100)) {
+        Err(mpsc::RecvTimeoutError::Timeout) => {}
+        Ok(()) => return Err("Thread acquired the lock while it was held".into()),
+        // The thread failed before locking, report its error
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            handle.join().map_err(|_| "Thread panicked".to_string())??;
+            return Err("Thread exited without acquiring the lock".into());
+        }
+    }
+    first.unlock()?;
+    if let Err(e) = receiver.recv_timeout(Duration::from_secs(5)) {
+        if e == mpsc::RecvTimeoutError::Disconnected {
+            handle.join().map_err(|_| "Thread panicked".to_string())??;
+        }
+        return Err("Thread did not acquire the lock after unlock".into());
+    }
+
+    let second = handle.join().map_err(|_| "Thread panicked".to_string())??;
+    assert_res!(is_would_block(first.try_lock_shared())?);
+    second.unlock()?;
+    assert_res!(!is_would_block(first.try_lock_shared())?);
+    Ok(())
+}
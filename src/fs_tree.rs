@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::fs::{CHECKSUM_INIT, checksum};
+use crate::{assert_eq_res, assert_res};
+
+/// Small xorshift generator for reproducible test data, the same seed gives the same sequence
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Rng(seed.wrapping_mul(0x9e3779b97f4a7c15) | 1)
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub(crate) fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ManifestEntry {
+    Dir,
+    File { size: u64, hash: u64 },
+}
+
+/// Paths relative to the tree root, with the type, size and hash of each entry
+pub(crate) type Manifest = BTreeMap<PathBuf, ManifestEntry>;
+
+/// Creates a random directory tree at `root`, the same seed always gives the same tree
+pub(crate) fn generate_tree(root: impl AsRef<Path>, seed: u64) -> std::io::Result<()> {
+    fn generate_dir(dir: &Path, rng: &mut Rng, depth: u32) -> std::io::Result<()> {
+        fs::create_dir(dir)?;
+        for i in 0..1 + rng.below(5) {
+            let size = match rng.below(4) {
+                0 => 0,
+                1 => rng.below(100),
+                _ => rng.below(10_000),
+            };
+            let content: Vec<u8> = (0..size).map(|_| rng.next() as u8).collect();
+            fs::write(
+                dir.join(format!("file_{i}_{}.bin", rng.below(1000))),
+                content,
+            )?;
+        }
+        if depth > 0 {
+            for i in 0..1 + rng.below(3) {
+                generate_dir(&dir.join(format!("dir_{i}")), rng, depth - 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut rng = Rng::new(seed);
+    generate_dir(root.as_ref(), &mut rng, 4)
+}
+
+/// Walks the tree at `root` recursively using `read_dir`
+pub(crate) fn tree_manifest(root: impl AsRef<Path>) -> std::io::Result<Manifest> {
+    fn walk(root: &Path, relative: &Path, manifest: &mut Manifest) -> std::io::Result<()> {
+        for entry in fs::read_dir(root.join(relative))? {
+            let entry = entry?;
+            let path = relative.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                manifest.insert(path.clone(), ManifestEntry::Dir);
+                walk(root, &path, manifest)?;
+            } else {
+                let content = fs::read(entry.path())?;
+                let mut hash = CHECKSUM_INIT;
+                checksum(&mut hash, &content);
+                let size = entry.metadata()?.len();
+                manifest.insert(path, ManifestEntry::File { size, hash });
+            }
+        }
+        Ok(())
+    }
+
+    let mut manifest = Manifest::new();
+    walk(root.as_ref(), Path::new(""), &mut manifest)?;
+    Ok(manifest)
+}
+
+/// Describes all differences between two manifests
+pub(crate) fn compare_manifests(expected: &Manifest, actual: &Manifest) -> Result<(), String> {
+    let mut differences = vec![];
+    for (path, entry) in expected {
+        match actual.get(path) {
+            None => differences.push(format!("{path:?} is missing")),
+            Some(actual_entry) if actual_entry != entry => differences.push(format!(
+                "{path:?} differs: expected {entry:?}, got {actual_entry:?}"
+            )),
+            Some(_) => {}
+        }
+    }
+    for path in actual.keys().filter(|path| !expected.contains_key(*path)) {
+        differences.push(format!("{path:?} is unexpected"));
+    }
+
+    if differences.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Trees differ:\n{}", differences.join("\n")))
+    }
+}
+
+/// Copies the tree at `src` to a new directory `dest`
+pub(crate) fn copy_tree(src: impl AsRef<Path>, dest: impl AsRef<Path>) -> std::io::Result<()> {
+    let (src, dest) = (src.as_ref(), dest.as_ref());
+    fs::create_dir(dest)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let dest_path = dest.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_tree(entry.path(), dest_path)?;
+        } else {
+            fs::copy(entry.path(), dest_path)?;
+        }
+    }
+    Ok(())
+}
+
+// Test that generating a tree is deterministic and the walk sees all of it
+pub fn test_tree_manifest() -> Result<(), Box<dyn std::error::Error>> {
+    let dir_path = "tree_manifest_test";
+    fs::create_dir(dir_path)?;
+    generate_tree(format!("{dir_path}/first"), 42)?;
+    generate_tree(format!("{dir_path}/second"), 42)?;
+    generate_tree(format!("{dir_path}/other"), 7)?;
+
+    let first = tree_manifest(format!("{dir_path}/first"))?;
+    assert_res!(first.values().any(|entry| *entry == ManifestEntry::Dir));
+    compare_manifests(&first, &tree_manifest(format!("{dir_path}/second"))?)?;
+    assert_res!(compare_manifests(&first, &tree_manifest(format!("{dir_path}/other"))?).is_err());
+
+    // Changing a single file is detected
+    let (path, _) = first
+        .iter()
+        .find(|(_, entry)| matches!(entry, ManifestEntry::File { size, .. } if *size > 0))
+        .ok_or("Generated tree has no non-empty files")?;
+    let mut content = fs::read(Path::new(dir_path).join("second").join(path))?;
+    content[0] ^= 1;
+    fs::write(Path::new(dir_path).join("second").join(path), content)?;
+    assert_res!(compare_manifests(&first, &tree_manifest(format!("{dir_path}/second"))?).is_err());
+    Ok(())
+}
+
+// Test copying a whole directory tree file by file
+pub fn test_copy_tree() -> Result<(), Box<dyn std::error::Error>> {
+    let dir_path = "copy_tree_test";
+    fs::create_dir(dir_path)?;
+    generate_tree(format!("{dir_path}/src"), 1)?;
+    copy_tree(format!("{dir_path}/src"), format!("{dir_path}/dest"))?;
+
+    let expected = tree_manifest(format!("{dir_path}/src"))?;
+    compare_manifests(&expected, &tree_manifest(format!("{dir_path}/dest"))?)?;
+    Ok(())
+}
+
+// Test removing a whole directory tree
+pub fn test_remove_dir_all_tree() -> Result<(), Box<dyn std::error::Error>> {
+    let dir_path = "remove_tree_test";
+    fs::create_dir(dir_path)?;
+    generate_tree(format!("{dir_path}/removed"), 2)?;
+    generate_tree(format!("{dir_path}/kept"), 3)?;
+    let kept = tree_manifest(format!("{dir_path}/kept"))?;
+
+    fs::remove_dir_all(format!("{dir_path}/removed"))?;
+    assert_res!(!Path::new(&format!("{dir_path}/removed")).exists());
+    // The sibling tree is untouched
+    compare_manifests(&kept, &tree_manifest(format!("{dir_path}/kept"))?)?;
+
+    let listed: Vec<_> = fs::read_dir(dir_path)?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<Result<_, _>>()?;
+    assert_eq_res!(listed, vec!["kept"]);
+    Ok(())
+}
+
+// Test renaming a directory tree into a different, deeper directory
+pub fn test_rename_tree() -> Result<(), Box<dyn std::error::Error>> {
+    let dir_path = "rename_tree_test";
+    fs::create_dir_all(format!("{dir_path}/a"))?;
+    fs::create_dir_all(format!("{dir_path}/b/c/d"))?;
+    generate_tree(format!("{dir_path}/a/tree"), 4)?;
+    let expected = tree_manifest(format!("{dir_path}/a/tree"))?;
+
+    fs::rename(
+        format!("{dir_path}/a/tree"),
+        format!("{dir_path}/b/c/d/moved"),
+    )?;
+    assert_res!(!Path::new(&format!("{dir_path}/a/tree")).exists());
+    compare_manifests(
+        &expected,
+        &tree_manifest(format!("{dir_path}/b/c/d/moved"))?,
+    )?;
+
+    fs::rename(format!("{dir_path}/b/c"), format!("{dir_path}/c"))?;
+    compare_manifests(&expected, &tree_manifest(format!("{dir_path}/c/d/moved"))?)?;
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::fs;
+use std::io::{self, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::{Skipped, assert_eq_res, assert_res};
+
+// Test reading from and writing to the null and zero devices
+pub fn test_null_device() -> Result<(), Box<dyn std::error::Error>> {
+    if !Path::new("/dev/null").exists() {
+        return Err(Skipped("/dev/null does not exist on this platform".into()).into());
+    }
+
+    let mut null = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/null")?;
+    assert_eq_res!(null.write(b"discarded")?, 9);
+    null.write_all(&[0; 100_000])?;
+    let mut buf = [0; 16];
+    assert_eq_res!(null.read(&mut buf)?, 0);
+
+    if Path::new("/dev/zero").exists() {
+        let mut zero = fs::File::open("/dev/zero")?;
+        let mut buf = [1; 4096];
+        zero.read_exact(&mut buf)?;
+        assert_res!(buf.iter().all(|&b| b == 0));
+    }
+    Ok(())
+}
+
+// Test that reading from a directory opened as a file fails
+pub fn test_read_directory_handle() -> Result<(), Box<dyn std::error::Error>> {
+    let dir_path = "read_directory_handle";
+    fs::create_dir(dir_path)?;
+    fs::write(format!("{dir_path}/file.txt"), "content")?;
+
+    // Opening may fail already, otherwise the read must
+    if let Ok(mut dir) = fs::File::open(dir_path) {
+        let mut content = vec![];
+        assert_res!(dir.read_to_end(&mut content).is_err());
+    }
+    assert_res!(fs::read(dir_path).is_err());
+    assert_res!(fs::read_to_string(dir_path).is_err());
+    Ok(())
+}
+
+// Test that cloned file handles share the file offset
+pub fn test_file_try_clone() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "test_file_try_clone.txt";
+    fs::write(file_path, "0123456789")?;
+
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(file_path)?;
+    let mut clone = file.try_clone()?;
+
+    let mut buf = [0; 3];
+    file.read_exact(&mut buf)?;
+    assert_eq_res!(&buf, b"012");
+    clone.read_exact(&mut buf)?;
+    assert_eq_res!(&buf, b"345");
+    assert_eq_res!(file.stream_position()?, 6);
+
+    clone.seek(SeekFrom::End(0))?;
+    file.write_all(b"ab")?;
+    drop(file);
+    clone.write_all(b"cd")?;
+    drop(clone);
+    assert_eq_res!(fs::read_to_string(file_path)?, "0123456789abcd");
+    Ok(())
+}
+
+// Uses the standard streams of a child process, see `test_stdio_handles`
+pub fn child_stdio(_args: &[String]) -> ! {
+    let mut input = String::new();
+    io::stdin()
+        .read_to_string(&mut input)
+        .expect("Failed to read stdin");
+
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "stdin: {input:?}").expect("Failed to write stdout");
+    write!(stdout, "stdout, terminal: {}", stdout.is_terminal()).expect("Failed to write stdout");
+    stdout.flush().expect("Failed to flush stdout");
+    drop(stdout);
+
+    let mut stderr = io::stderr();
+    write!(stderr, "stderr, terminal: {}", stderr.is_terminal()).expect("Failed to write stderr");
+    std::process::exit(0);
+}
+
+// Test that the standard streams of a child process with piped stdio are usable
+pub fn test_stdio_handles() -> Result<(), Box<dyn std::error::Error>> {
+    let output = crate::spawn_child("stdio", &[])?;
+    assert_res!(output.status.success());
+    assert_eq_res!(
+        String::from_utf8(output.stdout)?,
+        "stdin: \"\"\nstdout, terminal: false"
+    );
+    assert_eq_res!(String::from_utf8(output.stderr)?, "stderr, terminal: false");
+
+    let file = fs::File::create("test_stdio_handles.txt")?;
+    assert_res!(!file.is_terminal());
+    Ok(())
+}
+
+// Test io::copy between files, readers and sinks
+pub fn test_io_copy() -> Result<(), Box<dyn std::error::Error>> {
+    let src_path = "src_io_copy.txt";
+    let dest_path = "dest_io_copy.txt";
+    let content: String = (0..5000).map(|i| format!("line {i}\n")).collect();
+    fs::write(src_path, &content)?;
+
+    let mut src = fs::File::open(src_path)?;
+    let mut dest = fs::File::create(dest_path)?;
+    assert_eq_res!(io::copy(&mut src, &mut dest)?, content.len() as u64);
+    drop(dest);
+    assert_eq_res!(fs::read_to_string(dest_path)?, content);
+
+    src.rewind()?;
+    assert_eq_res!(io::copy(&mut src, &mut io::sink())?, content.len() as u64);
+    assert_eq_res!(io::copy(&mut src, &mut io::sink())?, 0);
+
+    let mut dest = fs::File::create(dest_path)?;
+    assert_eq_res!(
+        io::copy(&mut io::repeat(b'x').take(10_000), &mut dest)?,
+        10_000
+    );
+    assert_eq_res!(io::copy(&mut io::empty(), &mut dest)?, 0);
+    drop(dest);
+    assert_eq_res!(fs::read(dest_path)?, vec![b'x'; 10_000]);
+
+    let mut buf = vec![];
+    io::copy(&mut fs::File::open(src_path)?.take(12), &mut buf)?;
+    assert_eq_res!(buf, b"line 0\nline ");
+    Ok(())
+}
@@ -1,6 +1,15 @@
 use std::io::Write;
+use std::sync::OnceLock;
+use std::time::Instant;
 
+use report::{Status, json_record};
+
+mod bench;
 mod fs;
+mod fs_concurrent;
+mod fs_tree;
+mod io;
+mod report;
 mod thread;
 
 macro_rules! tests {
@@ -8,23 +17,164 @@ macro_rules! tests {
         [$((
             Box::new(|| {
                 ($name()).map_err(|e| Box::<dyn std::error::Error>::from(e))
-            }) as Box<dyn Fn() -> _>,
+            }) as Box<dyn Fn() -> Result<_, Box<dyn std::error::Error>>>,
             stringify!($name),
         )),*]
     };
 }
 
+static SCALE: OnceLock<u64> = OnceLock::new();
+
+/// Multiplier for the size of heavy tests, set by `--scale N` or the `RTEST_SCALE` env variable
+pub fn scale() -> u64 {
+    *SCALE.get().unwrap_or(&1)
+}
+
+#[derive(Default)]
+struct Options {
+    /// Print results as JSON lines instead of text
+    json: bool,
+    /// Run the filesystem benchmarks instead of the tests
+    bench: bool,
+}
+
+fn parse_args() -> Options {
+    let mut options = Options::default();
+    let mut scale = std::env::var("RTEST_SCALE")
+        .ok()
+        .map(|s| parse_scale(&s).expect("RTEST_SCALE must be a positive integer"));
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scale" => {
+                let value = args.next().expect("--scale requires a value");
+                scale = Some(parse_scale(&value).expect("--scale must be a positive integer"));
+            }
+            "--json" => options.json = true,
+            "--bench" => options.bench = true,
+            "--child" => {
+                let name = args.next().expect("--child requires a name");
+                run_child(&name, &args.collect::<Vec<_>>());
+            }
+            _ => {
+                eprintln!("Unknown argument: {arg}");
+                eprintln!("Usage: rtest [--json] [--bench] [--scale N]");
+                std::process::exit(2);
+            }
+        }
+    }
+    SCALE.set(scale.unwrap_or(1)).unwrap();
+    options
+}
+
+fn parse_scale(value: &str) -> Option<u64> {
+    value.parse().ok().filter(|&scale| scale > 0)
+}
+
+/// Runs the body of a test executed in a separate process, see `spawn_child`
+fn run_child(name: &str, args: &[String]) -> ! {
+    match name {
+        "sync_and_abort" => fs::child_sync_and_abort(args),
+        "stdio" => io::child_stdio(args),
+        _ => {
+            eprintln!("Unknown child process: {name}");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Runs `run_child(name, args)` in a new process of this executable and collects its output
+pub fn spawn_child(
+    name: &str,
+    args: &[&str],
+) -> Result<std::process::Output, Box<dyn std::error::Error>> {
+    let output = std::env::current_exe()
+        .and_then(|exe| {
+            std::process::Command::new(exe)
+                .arg("--child")
+                .arg(name)
+                .args(args)
+                .output()
+        })
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::Unsupported => {
+                Box::new(Skipped(format!("Cannot spawn child process: {e}"))) as _
+            }
+            _ => Box::<dyn std::error::Error>::from(e),
+        })?;
+    Ok(output)
+}
+
+fn run_benchmarks(json: bool) {
+    let mut failed = 0;
+    for (name, bench) in bench::benchmarks() {
+        if !json {
+            print!("Running {name}...");
+            let _ = std::io::stdout().flush();
+        }
+        let start = Instant::now();
+        let result = bench();
+        let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+        match result {
+            Ok(mut metrics) => {
+                metrics.insert(0, ("duration_ms", duration_ms));
+                if json {
+                    println!("{}", json_record(&name, Status::Ok, None, &metrics));
+                } else {
+                    let metrics: Vec<_> = metrics
+                        .iter()
+                        .map(|(metric, value)| format!("{metric}={value:.2}"))
+                        .collect();
+                    println!(" {}", metrics.join(" "));
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                if json {
+                    let message = e.to_string();
+                    let metrics = [("duration_ms", duration_ms)];
+                    println!(
+                        "{}",
+                        json_record(&name, Status::Failed, Some(&message), &metrics)
+                    );
+                } else {
+                    println!(" FAILED: {}", e);
+                }
+            }
+        }
+    }
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
 fn main() {
+    let options = parse_args();
+    // In JSON mode, stdout only contains the result records
+    let log = |message: &str| {
+        if options.json {
+            eprintln!("{message}");
+        } else {
+            println!("{message}");
+        }
+    };
+
     let temp_dir = std::env::temp_dir();
     let temp_dir_path = temp_dir.join("rust_file_tests");
-    println!("Using temporary directory: {:?}", temp_dir_path);
+    log(&format!("Using temporary directory: {:?}", temp_dir_path));
     if temp_dir_path.exists() {
-        println!("Cleaning up previous test files...");
+        log("Cleaning up previous test files...");
         std::fs::remove_dir_all(&temp_dir_path).expect("Failed to remove previous test files");
     }
     std::fs::create_dir_all(&temp_dir_path).expect("Failed to create temporary directory");
     std::env::set_current_dir(&temp_dir_path).expect("Failed to chdir to temporary directory");
 
+    if options.bench {
+        run_benchmarks(options.json);
+        return;
+    }
+
     let tests = tests![
         fs::test_create_file,
         fs::test_write_and_read_file,
@@ -42,45 +192,181 @@ fn main() {
         fs::test_create_nested_directories,
         fs::test_list_directory,
         fs::test_copy_file,
+        fs::test_copy_large_file,
+        fs::test_copy_empty_file,
+        fs::test_copy_overwrite,
+        fs::test_copy_invalid_paths,
+        fs::test_copy_onto_itself,
+        fs::test_copy_preserves_permissions,
         fs::test_move_file,
+        fs::test_rename_replace_file,
+        fs::test_rename_atomic_replace,
+        fs::test_rename_directory,
+        fs::test_rename_directory_onto_non_empty,
+        fs::test_rename_into_own_subdirectory,
+        fs::test_rename_across_directories,
+        fs::test_rename_with_open_handle,
         fs::test_empty_directory,
         fs::test_read_empty_file,
         fs::test_directory_listing_after_removal,
         fs::test_file_replacement,
+        fs::test_open_options_matrix,
+        fs::test_file_seek,
+        fs::test_file_seek_past_end,
+        fs::test_file_set_len,
+        fs::test_file_positional_io,
+        fs::test_file_vectored_io,
+        fs::test_file_read_exact_eof,
+        fs::test_file_partial_io,
+        fs::test_bufreader_small_capacity,
+        fs::test_bufwriter_flush,
+        fs::test_write_and_read_scaled_file,
+        fs::test_sparse_file_beyond_4gib,
+        fs::test_list_large_directory,
+        fs::test_remove_during_directory_iteration,
+        fs::test_unicode_file_names,
+        fs::test_long_file_name,
+        fs::test_deeply_nested_path,
+        fs::test_path_components,
+        fs::test_canonicalize_and_absolute,
+        fs::test_set_current_dir,
+        fs::test_set_current_dir_missing,
+        fs::test_current_dir_in_threads,
+        fs::test_file_sync,
+        fs::test_directory_sync,
+        fs::test_bufwriter_flush_error,
+        fs::test_sync_survives_abort,
+        fs_tree::test_tree_manifest,
+        fs_tree::test_copy_tree,
+        fs_tree::test_remove_dir_all_tree,
+        fs_tree::test_rename_tree,
+        fs_concurrent::test_concurrent_append,
+        fs_concurrent::test_concurrent_create_remove,
+        fs_concurrent::test_concurrent_read_dir,
+        fs_concurrent::test_concurrent_rename,
+        fs_concurrent::test_file_lock_exclusive,
+        fs_concurrent::test_file_lock_shared,
+        fs_concurrent::test_file_lock_released_on_drop,
+        fs_concurrent::test_file_lock_between_threads,
+        io::test_null_device,
+        io::test_read_directory_handle,
+        io::test_file_try_clone,
+        io::test_stdio_handles,
+        io::test_io_copy,
         thread::test_create_thread,
         thread::test_mutex_counter,
         thread::test_scheduling,
         thread::test_condvar,
+        thread::test_condvar_wait_timeout,
+        thread::test_condvar_wait_timeout_while,
+        thread::test_condvar_wait_while,
+        thread::test_condvar_notify_all,
+        thread::test_condvar_bounded_queue,
         thread::test_thread_join,
         thread::test_thread_sleep,
         thread::test_rwlock,
+        thread::test_mutex_poisoning,
+        thread::test_rwlock_poisoning,
+        thread::test_mutex_try_lock,
+        thread::test_rwlock_try_lock,
+        thread::test_rwlock_concurrent_readers,
+        thread::test_rwlock_writer_not_starved,
+        thread::test_rwlock_downgrade,
+        thread::test_atomic_counters,
+        thread::test_atomic_compare_exchange,
+        thread::test_atomic_ptr,
+        thread::test_atomic_message_passing,
+        thread::test_atomic_u64,
         thread::test_channel,
+        thread::test_sync_channel_rendezvous,
+        thread::test_sync_channel_bounded,
+        thread::test_channel_multiple_senders,
+        thread::test_channel_try_recv,
+        thread::test_channel_send_after_receiver_drop,
+        thread::test_channel_throughput,
         thread::test_scoped_oncelock,
+        thread::test_scoped_parallel_sum,
+        thread::test_scope_joins_automatically,
+        thread::test_scoped_thread_panic,
+        thread::test_nested_scopes,
+        thread::test_oncelock_race,
+        thread::test_oncelock_set,
+        thread::test_once_poisoning,
+        thread::test_lazylock,
         thread::test_barrier,
         thread::test_thread_local_storage,
+        thread::test_thread_local_destructors,
+        thread::test_thread_local_destructor_order,
+        thread::test_thread_local_access_in_destructor,
+        thread::test_thread_builder_name,
+        thread::test_thread_stack_size,
+        thread::test_thread_builder_spawn_error,
+        thread::test_thread_id_unique,
+        thread::test_current_main_thread,
+        thread::test_thread_park,
+        thread::test_unpark_before_park,
+        thread::test_park_timeout,
+        thread::test_yield_now,
     ];
 
     let mut failed = 0;
+    let mut skipped = 0;
     for (func, name) in tests {
-        print!("Running {name}...");
-        let _ = std::io::stdout().flush();
-        match func() {
-            Ok(_) => println!(" OK"),
+        if !options.json {
+            print!("Running {name}...");
+            let _ = std::io::stdout().flush();
+        }
+        let start = Instant::now();
+        let result = func();
+        let metrics = [("duration_ms", start.elapsed().as_secs_f64() * 1000.0)];
+        let (status, message) = match &result {
+            Ok(_) => (Status::Ok, None),
+            Err(e) if e.is::<Skipped>() => {
+                skipped += 1;
+                (Status::Skipped, Some(e.to_string()))
+            }
             Err(e) => {
                 failed += 1;
-                println!(" FAILED: {}", e);
+                (Status::Failed, Some(e.to_string()))
+            }
+        };
+        if options.json {
+            println!(
+                "{}",
+                json_record(name, status, message.as_deref(), &metrics)
+            );
+        } else {
+            match (status, message) {
+                (Status::Ok, _) => println!(" OK"),
+                (Status::Skipped, Some(message)) => println!(" SKIPPED: {}", message),
+                (_, message) => println!(" FAILED: {}", message.unwrap_or_default()),
             }
         }
     }
 
+    if skipped > 0 {
+        log(&format!("{skipped} tests skipped."));
+    }
     if failed == 0 {
-        println!("All tests passed!");
+        log("All tests passed!");
     } else {
-        println!("{failed} tests failed.");
+        log(&format!("{failed} tests failed."));
         std::process::exit(1);
     }
 }
 
+/// Error returned by tests which cannot run on the current platform
+#[derive(Debug)]
+pub struct Skipped(pub String);
+
+impl std::fmt::Display for Skipped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Skipped {}
+
 #[macro_export]
 macro_rules! assert_res {
     ($cond:expr $(,)?) => {
@@ -0,0 +1,64 @@
+// Machine-readable results, printed as one JSON object per line.
+// Tests and benchmarks use the same record, benchmarks just add more metrics.
+
+use std::fmt::Write;
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Status {
+    Ok,
+    Failed,
+    Skipped,
+}
+
+impl Status {
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Failed => "failed",
+            Status::Skipped => "skipped",
+        }
+    }
+}
+
+/// Formats `{"name": ..., "status": ..., "message": ..., <metrics>...}`, without a newline
+pub fn json_record(
+    name: &str,
+    status: Status,
+    message: Option<&str>,
+    metrics: &[(&str, f64)],
+) -> String {
+    let mut record = format!(
+        "{{\"name\": \"{}\", \"status\": \"{}\"",
+        escape(name),
+        status.as_str()
+    );
+    if let Some(message) = message {
+        write!(record, ", \"message\": \"{}\"", escape(message)).unwrap();
+    }
+    for (metric, value) in metrics {
+        // JSON has no representation for NaN or infinity
+        if value.is_finite() {
+            write!(record, ", \"{}\": {}", escape(metric), value).unwrap();
+        } else {
+            write!(record, ", \"{}\": null", escape(metric)).unwrap();
+        }
+    }
+    record.push('}');
+    record
+}
@@ -2,7 +2,8 @@
 // The author has then verified the correctness of the code and
 // added some tests to cover missing functionality.
 
-use std::sync::{Arc, Barrier, Condvar, Mutex, OnceLock, RwLock, mpsc};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier, Condvar, Mutex, OnceLock, RwLock, TryLockError, mpsc};
 use std::thread;
 use std::time::Duration;
 
@@ -288,6 +289,8 @@ pub fn test_thread_local_storage() -> Result<(), Box<dyn std::error::Error>> {
 
     // Define thread-local storage
     thread_local! {
+        // Lazily initialized on purpose, this test covers that TLS path
+        #[allow(clippy::missing_const_for_thread_local)]
         static THREAD_LOCAL: RefCell<usize> = RefCell::new(0);
     }
 
@@ -320,3 +323,1766 @@ pub fn test_thread_local_storage() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+// Test naming threads with Builder
+pub fn test_thread_builder_name() -> Result<(), Box<dyn std::error::Error>> {
+    let handle = thread::Builder::new()
+        .name("named-worker".to_string())
+        .spawn(|| thread::current().name().map(String::from))?;
+    assert_eq_res!(handle.thread().name(), Some("named-worker"));
+    let name = handle.join().map_err(|_| "Thread panicked".to_string())?;
+    assert_eq_res!(name, Some("named-worker".to_string()));
+
+    let handle = thread::Builder::new()
+        .name("žluťoučký 🧵".to_string())
+        .spawn(|| thread::current().name().map(String::from))?;
+    let name = handle.join().map_err(|_| "Thread panicked".to_string())?;
+    assert_eq_res!(name, Some("žluťoučký 🧵".to_string()));
+
+    let handle = thread::spawn(|| thread::current().name().map(String::from));
+    assert_eq_res!(handle.thread().name(), None);
+    let name = handle.join().map_err(|_| "Thread panicked".to_string())?;
+    assert_eq_res!(name, None);
+    Ok(())
+}
+
+// Recursion with a large frame, which needs a lot of stack
+fn deep_recursion(depth: usize) -> usize {
+    let frame = std::hint::black_box([depth as u8; 1024]);
+    if depth == 0 {
+        0
+    } else {
+        deep_recursion(depth - 1) + usize::from(frame[0] & 1)
+    }
+}
+
+// Test spawning threads with small and large stacks
+pub fn test_thread_stack_size() -> Result<(), Box<dyn std::error::Error>> {
+    let handle = thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn(|| deep_recursion(10))?;
+    let result = handle.join().map_err(|_| "Thread panicked".to_string())?;
+    assert_eq_res!(result, 5);
+
+    // About 20 MiB of stack, far more than the default 2 MiB
+    let depth = 20_000;
+    let handle = thread::Builder::new()
+        .stack_size(64 * 1024 * 1024)
+        .spawn(move || deep_recursion(depth))?;
+    let result = handle.join().map_err(|_| "Thread panicked".to_string())?;
+    assert_eq_res!(result, depth / 2);
+    Ok(())
+}
+
+// Test that Builder::spawn reports failure to create a thread
+pub fn test_thread_builder_spawn_error() -> Result<(), Box<dyn std::error::Error>> {
+    // Almost the whole address space, page aligned, which no target can map
+    let result = thread::Builder::new()
+        .stack_size(usize::MAX & !0xffff)
+        .spawn(|| ());
+    match result {
+        Ok(handle) => {
+            handle.join().map_err(|_| "Thread panicked".to_string())?;
+            return Err("Spawned a thread with an impossible stack size".into());
+        }
+        // Out of address space is reported as EAGAIN or ENOMEM, an oversized request as EINVAL
+        Err(e) => match e.kind() {
+            std::io::ErrorKind::WouldBlock
+            | std::io::ErrorKind::OutOfMemory
+            | std::io::ErrorKind::InvalidInput => {}
+            _ => return Err(format!("Unexpected error for an impossible stack size: {e}").into()),
+        },
+    }
+
+    // Spawning still works after the failure
+    let handle = thread::Builder::new().spawn(|| 42)?;
+    assert_eq_res!(
+        handle.join().map_err(|_| "Thread panicked".to_string())?,
+        42
+    );
+    Ok(())
+}
+
+// Test that every thread gets a unique ThreadId
+pub fn test_thread_id_unique() -> Result<(), Box<dyn std::error::Error>> {
+    use std::collections::HashSet;
+
+    let main_id = thread::current().id();
+    let mut ids = HashSet::from([main_id]);
+    let barrier = Arc::new(Barrier::new(THREAD_COUNT * 10));
+    let handles: Vec<_> = (0..THREAD_COUNT * 10)
+        .map(|_| {
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                // Keep all threads alive at once
+                barrier.wait();
+                thread::current().id()
+            })
+        })
+        .collect();
+    for handle in handles {
+        let handle_id = handle.thread().id();
+        let id = handle.join().map_err(|_| "Thread panicked".to_string())?;
+        assert_eq_res!(id, handle_id);
+        assert_res!(ids.insert(id));
+    }
+
+    // Ids of finished threads are not reused
+    for _ in 0..THREAD_COUNT * 10 {
+        let id = thread::spawn(|| thread::current().id())
+            .join()
+            .map_err(|_| "Thread panicked".to_string())?;
+        assert_res!(ids.insert(id));
+    }
+    assert_eq_res!(thread::current().id(), main_id);
+    Ok(())
+}
+
+// Test thread::current on the main thread
+pub fn test_current_main_thread() -> Result<(), Box<dyn std::error::Error>> {
+    let current = thread::current();
+    assert_eq_res!(current.name(), Some("main"));
+    assert_eq_res!(current.id(), thread::current().id());
+
+    let main_id = current.id();
+    let other_id = thread::spawn(|| thread::current().id())
+        .join()
+        .map_err(|_| "Thread panicked".to_string())?;
+    assert_res!(other_id != main_id);
+    Ok(())
+}
+
+// Test waking a parked thread with unpark
+pub fn test_thread_park() -> Result<(), Box<dyn std::error::Error>> {
+    let ready = Arc::new(AtomicBool::new(false));
+    let ready_thread = Arc::clone(&ready);
+    let handle = thread::spawn(move || {
+        // park can return spuriously, so the condition has to be checked in a loop
+        while !ready_thread.load(Ordering::Acquire) {
+            thread::park();
+        }
+    });
+
+    thread::sleep(Duration::from_millis(20));
+    ready.store(true, Ordering::Release);
+    handle.thread().unpark();
+    handle.join().map_err(|_| "Thread panicked".to_string())?;
+    Ok(())
+}
+
+// Test that unpark before park leaves a token, so the next park returns immediately
+pub fn test_unpark_before_park() -> Result<(), Box<dyn std::error::Error>> {
+    const ROUNDS: usize = 3;
+    let timeout = Duration::from_millis(100);
+    let round = Arc::new(AtomicUsize::new(0));
+    let round_thread = Arc::clone(&round);
+    let (sender, receiver) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        for r in 1..=ROUNDS {
+            // Spin instead of blocking, blocking primitives may park and consume the token
+            while round_thread.load(Ordering::Acquire) != r {
+                thread::yield_now();
+            }
+            let start = std::time::Instant::now();
+            thread::park_timeout(Duration::from_secs(5));
+            let with_token = start.elapsed();
+
+            // The token is used up now, so parking has to wait until the deadline
+            let start = std::time::Instant::now();
+            let mut parks = 0;
+            while start.elapsed() < timeout {
+                thread::park_timeout(timeout - start.elapsed().min(timeout));
+                parks += 1;
+            }
+            sender
+                .send((with_token, parks))
+                .map_err(|_| "Failed to send message".to_string())?;
+        }
+        Ok::<_, String>(())
+    });
+
+    let mut early_returns = 0;
+    for r in 1..=ROUNDS {
+        handle.thread().unpark();
+        // Multiple unparks do not add up, there is only a single token
+        handle.thread().unpark();
+        round.store(r, Ordering::Release);
+        let (with_token, parks) = receiver
+            .recv()
+            .map_err(|_| "Failed to receive message".to_string())?;
+        assert_res!(with_token < Duration::from_secs(1));
+        if parks > 1 {
+            early_returns += 1;
+        }
+    }
+    handle.join().map_err(|_| "Thread panicked".to_string())??;
+    // A second token makes every round return early, a spurious wakeup only single rounds
+    if early_returns == ROUNDS {
+        return Err(format!(
+            "park_timeout returned early after every double unpark, a second token was left\n{}",
+            "This test is timing-based, try re-running it on an idle system"
+        )
+        .into());
+    }
+
+    // The main thread can consume its own token too
+    thread::current().unpark();
+    let start = std::time::Instant::now();
+    thread::park();
+    assert_res!(start.elapsed() < Duration::from_secs(1));
+    Ok(())
+}
+
+// Test that park_timeout returns after the timeout when nobody unparks
+pub fn test_park_timeout() -> Result<(), Box<dyn std::error::Error>> {
+    let timeout = Duration::from_millis(50);
+    let start = std::time::Instant::now();
+    // Spurious wakeups are allowed, so keep parking until the deadline passes
+    let mut parks = 0;
+    while start.elapsed() < timeout {
+        thread::park_timeout(timeout - start.elapsed().min(timeout));
+        parks += 1;
+    }
+    let elapsed = start.elapsed();
+    // A park_timeout which returns immediately would spin here thousands of times
+    if parks > 100 {
+        return Err(format!(
+            "park_timeout returned early, took {parks} calls to wait {timeout:?}\n{}",
+            "This test is timing-based, try re-running it on an idle system"
+        )
+        .into());
+    }
+    if elapsed >= timeout * 4 {
+        return Err(format!(
+            "park_timeout of {timeout:?} took {elapsed:?}\n{}",
+            "This test is timing-based, try re-running it on an idle system"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+// Test threads passing a turn around while spinning with yield_now
+pub fn test_yield_now() -> Result<(), Box<dyn std::error::Error>> {
+    let turn = Arc::new(AtomicUsize::new(0));
+    let mut handles = vec![];
+
+    for i in 0..THREAD_COUNT {
+        let turn = Arc::clone(&turn);
+        let handle = thread::spawn(move || {
+            for round in 0..ITER_COUNT {
+                let my_turn = round * THREAD_COUNT + i;
+                // Without yielding, a thread waiting for its turn could starve the others
+                while turn.load(Ordering::Acquire) != my_turn {
+                    thread::yield_now();
+                }
+                turn.store(my_turn + 1, Ordering::Release);
+            }
+        });
+        handles.push(handle);
+    }
+
+    for handle in handles {
+        handle.join().map_err(|_| "Thread panicked".to_string())?;
+    }
+    assert_eq_res!(turn.load(Ordering::Acquire), THREAD_COUNT * ITER_COUNT);
+    Ok(())
+}
+
+// Test Condvar::wait_timeout both timing out and being notified
+pub fn test_condvar_wait_timeout() -> Result<(), Box<dyn std::error::Error>> {
+    let timeout = Duration::from_millis(50);
+    let lock = Mutex::new(());
+    let cvar = Condvar::new();
+
+    let start = std::time::Instant::now();
+    let mut guard = lock
+        .lock()
+        .map_err(|_| "Failed to lock mutex".to_string())?;
+    loop {
+        let remaining = timeout.saturating_sub(start.elapsed());
+        let (new_guard, result) = cvar
+            .wait_timeout(guard, remaining)
+            .map_err(|_| "Failed to wait".to_string())?;
+        guard = new_guard;
+        // Without a notification, only a spurious wakeup may end the wait early
+        if result.timed_out() {
+            break;
+        }
+    }
+    drop(guard);
+    let elapsed = start.elapsed();
+    assert_res!(elapsed >= timeout);
+    if elapsed >= timeout * 4 {
+        return Err(format!(
+            "wait_timeout of {timeout:?} took {elapsed:?}\n{}",
+            "This test is timing-based, try re-running it on an idle system"
+        )
+        .into());
+    }
+
+    let pair = Arc::new((Mutex::new(false), Condvar::new()));
+    let pair_thread = Arc::clone(&pair);
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        let (lock, cvar) = &*pair_thread;
+        *lock
+            .lock()
+            .map_err(|_| "Failed to lock mutex".to_string())? = true;
+        cvar.notify_one();
+        Ok::<_, String>(())
+    });
+
+    let (lock, cvar) = &*pair;
+    let mut notified = lock
+        .lock()
+        .map_err(|_| "Failed to lock mutex".to_string())?;
+    let start = std::time::Instant::now();
+    while !*notified {
+        let (new_guard, result) = cvar
+            .wait_timeout(notified, Duration::from_secs(5))
+            .map_err(|_| "Failed to wait".to_string())?;
+        notified = new_guard;
+        assert_res!(!result.timed_out());
+    }
+    assert_res!(start.elapsed() < Duration::from_secs(5));
+    drop(notified);
+    handle.join().map_err(|_| "Thread panicked".to_string())??;
+    Ok(())
+}
+
+// Test Condvar::wait_timeout_while with a condition that does and does not become false
+pub fn test_condvar_wait_timeout_while() -> Result<(), Box<dyn std::error::Error>> {
+    let pair = Arc::new((Mutex::new(0), Condvar::new()));
+    let (lock, cvar) = &*pair;
+
+    let guard = lock
+        .lock()
+        .map_err(|_| "Failed to lock mutex".to_string())?;
+    let start = std::time::Instant::now();
+    let (guard, result) = cvar
+        .wait_timeout_while(guard, Duration::from_millis(30), |value| *value == 0)
+        .map_err(|_| "Failed to wait".to_string())?;
+    assert_res!(result.timed_out());
+    assert_res!(start.elapsed() >= Duration::from_millis(30));
+    assert_eq_res!(*guard, 0);
+    drop(guard);
+
+    let pair_thread = Arc::clone(&pair);
+    let handle = thread::spawn(move || {
+        let (lock, cvar) = &*pair_thread;
+        for _ in 0..3 {
+            thread::sleep(Duration::from_millis(5));
+            *lock
+                .lock()
+                .map_err(|_| "Failed to lock mutex".to_string())? += 1;
+            cvar.notify_one();
+        }
+        Ok::<_, String>(())
+    });
+
+    let guard = lock
+        .lock()
+        .map_err(|_| "Failed to lock mutex".to_string())?;
+    let (guard, result) = cvar
+        .wait_timeout_while(guard, Duration::from_secs(5), |value| *value < 3)
+        .map_err(|_| "Failed to wait".to_string())?;
+    assert_res!(!result.timed_out());
+    assert_eq_res!(*guard, 3);
+    drop(guard);
+    handle.join().map_err(|_| "Thread panicked".to_string())??;
+    Ok(())
+}
+
+// Test Condvar::wait_while until all threads updated the shared value
+pub fn test_condvar_wait_while() -> Result<(), Box<dyn std::error::Error>> {
+    let pair = Arc::new((Mutex::new(0), Condvar::new()));
+    let mut handles = vec![];
+
+    for _ in 0..THREAD_COUNT {
+        let pair = Arc::clone(&pair);
+        let handle = thread::spawn(move || {
+            let (lock, cvar) = &*pair;
+            for _ in 0..ITER_COUNT {
+                *lock
+                    .lock()
+                    .map_err(|_| "Failed to lock mutex".to_string())? += 1;
+                cvar.notify_all();
+            }
+            Ok::<_, String>(())
+        });
+        handles.push(handle);
+    }
+
+    let (lock, cvar) = &*pair;
+    let guard = lock
+        .lock()
+        .map_err(|_| "Failed to lock mutex".to_string())?;
+    let guard = cvar
+        .wait_while(guard, |value| *value < THREAD_COUNT * ITER_COUNT)
+        .map_err(|_| "Failed to wait".to_string())?;
+    assert_eq_res!(*guard, THREAD_COUNT * ITER_COUNT);
+    drop(guard);
+
+    for handle in handles {
+        handle.join().map_err(|_| "Thread panicked".to_string())??;
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct WaitState {
+    waiting: usize,
+    go: bool,
+    woken: usize,
+}
+
+// Test that notify_all wakes every waiting thread
+pub fn test_condvar_notify_all() -> Result<(), Box<dyn std::error::Error>> {
+    let state = Arc::new((
+        Mutex::new(WaitState::default()),
+        Condvar::new(),
+        Condvar::new(),
+    ));
+    let mut handles = vec![];
+
+    for _ in 0..THREAD_COUNT {
+        let state = Arc::clone(&state);
+        let handle = thread::spawn(move || {
+            let (lock, go_cvar, woken_cvar) = &*state;
+            let mut guard = lock
+                .lock()
+                .map_err(|_| "Failed to lock mutex".to_string())?;
+            guard.waiting += 1;
+            woken_cvar.notify_all();
+            let mut guard = go_cvar
+                .wait_while(guard, |state| !state.go)
+                .map_err(|_| "Failed to wait".to_string())?;
+            guard.woken += 1;
+            woken_cvar.notify_all();
+            Ok::<_, String>(())
+        });
+        handles.push(handle);
+    }
+
+    let (lock, go_cvar, woken_cvar) = &*state;
+    let guard = lock
+        .lock()
+        .map_err(|_| "Failed to lock mutex".to_string())?;
+    // The waiting count is increased under the lock right before waiting,
+    // so once we hold the lock with all of them counted, all threads are in wait
+    let mut guard = woken_cvar
+        .wait_while(guard, |state| state.waiting < THREAD_COUNT)
+        .map_err(|_| "Failed to wait".to_string())?;
+    guard.go = true;
+    go_cvar.notify_all();
+
+    let (guard, result) = woken_cvar
+        .wait_timeout_while(guard, Duration::from_secs(5), |state| {
+            state.woken < THREAD_COUNT
+        })
+        .map_err(|_| "Failed to wait".to_string())?;
+    if result.timed_out() {
+        return Err(format!(
+            "Only {} of {THREAD_COUNT} threads woke up after notify_all",
+            guard.woken
+        )
+        .into());
+    }
+    drop(guard);
+
+    for handle in handles {
+        handle.join().map_err(|_| "Thread panicked".to_string())??;
+    }
+    Ok(())
+}
+
+// Test a bounded queue built on Mutex and Condvar, which hangs on lost wakeups
+pub fn test_condvar_bounded_queue() -> Result<(), Box<dyn std::error::Error>> {
+    use std::collections::VecDeque;
+
+    const CAPACITY: usize = 4;
+    const ITEM_COUNT: usize = 5000;
+    const TIMEOUT: Duration = Duration::from_secs(10);
+    let producer_count = THREAD_COUNT / 2;
+    let consumer_count = THREAD_COUNT - producer_count;
+    let total = producer_count * ITEM_COUNT;
+
+    // Queue with the number of items already taken out, guarded by one mutex
+    let queue = Arc::new((
+        Mutex::new((VecDeque::with_capacity(CAPACITY), 0)),
+        Condvar::new(),
+        Condvar::new(),
+    ));
+    let mut producers = vec![];
+    let mut consumers = vec![];
+
+    for p in 0..producer_count {
+        let queue = Arc::clone(&queue);
+        producers.push(thread::spawn(move || {
+            let (lock, not_full, not_empty) = &*queue;
+            for i in 0..ITEM_COUNT {
+                let guard = lock
+                    .lock()
+                    .map_err(|_| "Failed to lock mutex".to_string())?;
+                let (mut guard, result) = not_full
+                    .wait_timeout_while(guard, TIMEOUT, |(queue, _)| queue.len() == CAPACITY)
+                    .map_err(|_| "Failed to wait".to_string())?;
+                if result.timed_out() {
+                    return Err(format!("Producer {p} lost a wakeup at item {i}"));
+                }
+                guard.0.push_back(p * ITEM_COUNT + i);
+                not_empty.notify_one();
+            }
+            Ok::<_, String>(())
+        }));
+    }
+
+    for c in 0..consumer_count {
+        let queue = Arc::clone(&queue);
+        consumers.push(thread::spawn(move || {
+            let (lock, not_full, not_empty) = &*queue;
+            let mut received = vec![];
+            loop {
+                let guard = lock
+                    .lock()
+                    .map_err(|_| "Failed to lock mutex".to_string())?;
+                let (mut guard, result) = not_empty
+                    .wait_timeout_while(guard, TIMEOUT, |(queue, taken)| {
+                        queue.is_empty() && *taken < total
+                    })
+                    .map_err(|_| "Failed to wait".to_string())?;
+                if result.timed_out() {
+                    return Err(format!("Consumer {c} lost a wakeup"));
+                }
+                let Some(item) = guard.0.pop_front() else {
+                    // Everything was taken, wake up the other consumers to finish too
+                    not_empty.notify_all();
+                    return Ok(received);
+                };
+                guard.1 += 1;
+                not_full.notify_one();
+                received.push(item);
+            }
+        }));
+    }
+
+    for handle in producers {
+        handle
+            .join()
+            .map_err(|_| "Producer thread panicked".to_string())??;
+    }
+    let mut received = vec![];
+    for handle in consumers {
+        received.extend(
+            handle
+                .join()
+                .map_err(|_| "Consumer thread panicked".to_string())??,
+        );
+    }
+
+    received.sort_unstable();
+    assert_eq_res!(received, (0..total).collect::<Vec<_>>());
+    Ok(())
+}
+
+// Runs `f` without printing messages of panics it causes, for tests that panic on purpose
+fn silence_panics<R>(f: impl FnOnce() -> R) -> R {
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let result = f();
+    std::panic::set_hook(previous_hook);
+    result
+}
+
+// Test that a thread panicking while holding a Mutex poisons it, and recovering from that
+pub fn test_mutex_poisoning() -> Result<(), Box<dyn std::error::Error>> {
+    let mutex = Arc::new(Mutex::new(0));
+    let mutex_thread = Arc::clone(&mutex);
+    let result = silence_panics(|| {
+        thread::spawn(move || {
+            let mut guard = mutex_thread.lock().unwrap();
+            *guard = 1;
+            panic!("Panicking while holding the lock");
+        })
+        .join()
+    });
+    assert_res!(result.is_err());
+    assert_res!(mutex.is_poisoned());
+
+    let mut guard = match mutex.lock() {
+        Ok(_) => return Err("Locking a poisoned mutex succeeded".into()),
+        Err(e) => e.into_inner(),
+    };
+    // The change made before the panic is visible
+    assert_eq_res!(*guard, 1);
+    *guard = 2;
+    drop(guard);
+    assert_res!(mutex.is_poisoned());
+
+    mutex.clear_poison();
+    assert_res!(!mutex.is_poisoned());
+    let guard = mutex
+        .lock()
+        .map_err(|_| "Failed to lock mutex".to_string())?;
+    assert_eq_res!(*guard, 2);
+    Ok(())
+}
+
+// Test poisoning of RwLock, which only happens when panicking with a write guard
+pub fn test_rwlock_poisoning() -> Result<(), Box<dyn std::error::Error>> {
+    let rwlock = Arc::new(RwLock::new(0));
+
+    let rwlock_thread = Arc::clone(&rwlock);
+    let result = silence_panics(|| {
+        thread::spawn(move || {
+            let _guard = rwlock_thread.read().unwrap();
+            panic!("Panicking while holding a read lock");
+        })
+        .join()
+    });
+    assert_res!(result.is_err());
+    assert_res!(!rwlock.is_poisoned());
+
+    let rwlock_thread = Arc::clone(&rwlock);
+    let result = silence_panics(|| {
+        thread::spawn(move || {
+            let mut guard = rwlock_thread.write().unwrap();
+            *guard = 1;
+            panic!("Panicking while holding a write lock");
+        })
+        .join()
+    });
+    assert_res!(result.is_err());
+    assert_res!(rwlock.is_poisoned());
+
+    let value = match rwlock.read() {
+        Ok(_) => return Err("Reading a poisoned RwLock succeeded".into()),
+        Err(e) => *e.into_inner(),
+    };
+    assert_eq_res!(value, 1);
+    assert_res!(rwlock.write().is_err());
+
+    rwlock.clear_poison();
+    assert_res!(!rwlock.is_poisoned());
+    *rwlock
+        .write()
+        .map_err(|_| "Failed to lock RwLock for writing".to_string())? += 1;
+    let value = *rwlock
+        .read()
+        .map_err(|_| "Failed to lock RwLock for reading".to_string())?;
+    assert_eq_res!(value, 2);
+    Ok(())
+}
+
+// Test Mutex::try_lock while the mutex is held, free and poisoned
+pub fn test_mutex_try_lock() -> Result<(), Box<dyn std::error::Error>> {
+    let mutex = Arc::new(Mutex::new(0));
+    let guard = mutex
+        .lock()
+        .map_err(|_| "Failed to lock mutex".to_string())?;
+
+    let mutex_thread = Arc::clone(&mutex);
+    let would_block =
+        thread::spawn(move || matches!(mutex_thread.try_lock(), Err(TryLockError::WouldBlock)))
+            .join()
+            .map_err(|_| "Thread panicked".to_string())?;
+    assert_res!(would_block);
+    // The mutex is not reentrant, the owning thread can't lock it again either
+    assert_res!(matches!(mutex.try_lock(), Err(TryLockError::WouldBlock)));
+    drop(guard);
+
+    let mutex_thread = Arc::clone(&mutex);
+    let locked =
+        thread::spawn(move || mutex_thread.try_lock().map(|mut guard| *guard += 1).is_ok())
+            .join()
+            .map_err(|_| "Thread panicked".to_string())?;
+    assert_res!(locked);
+
+    let mutex_thread = Arc::clone(&mutex);
+    let _ = silence_panics(|| {
+        thread::spawn(move || {
+            let _guard = mutex_thread.lock().unwrap();
+            panic!("Panicking while holding the lock");
+        })
+        .join()
+    });
+    match mutex.try_lock() {
+        Err(TryLockError::Poisoned(e)) => assert_eq_res!(*e.into_inner(), 1),
+        Err(TryLockError::WouldBlock) => return Err("Poisoned mutex is still locked".into()),
+        Ok(_) => return Err("try_lock on a poisoned mutex succeeded".into()),
+    }
+    Ok(())
+}
+
+// Test RwLock::try_read and try_write against held read and write guards
+pub fn test_rwlock_try_lock() -> Result<(), Box<dyn std::error::Error>> {
+    let rwlock = Arc::new(RwLock::new(0));
+
+    let read_guard = rwlock
+        .read()
+        .map_err(|_| "Failed to lock RwLock for reading".to_string())?;
+    let rwlock_thread = Arc::clone(&rwlock);
+    let (read_ok, write_blocked) = thread::spawn(move || {
+        let read_ok = rwlock_thread.try_read().is_ok();
+        let write_blocked = matches!(rwlock_thread.try_write(), Err(TryLockError::WouldBlock));
+        (read_ok, write_blocked)
+    })
+    .join()
+    .map_err(|_| "Thread panicked".to_string())?;
+    assert_res!(read_ok);
+    assert_res!(write_blocked);
+    drop(read_guard);
+
+    let write_guard = rwlock
+        .write()
+        .map_err(|_| "Failed to lock RwLock for writing".to_string())?;
+    let rwlock_thread = Arc::clone(&rwlock);
+    let (read_blocked, write_blocked) = thread::spawn(move || {
+        let read_blocked = matches!(rwlock_thread.try_read(), Err(TryLockError::WouldBlock));
+        let write_blocked = matches!(rwlock_thread.try_write(), Err(TryLockError::WouldBlock));
+        (read_blocked, write_blocked)
+    })
+    .join()
+    .map_err(|_| "Thread panicked".to_string())?;
+    assert_res!(read_blocked);
+    assert_res!(write_blocked);
+    drop(write_guard);
+
+    let rwlock_thread = Arc::clone(&rwlock);
+    let written = thread::spawn(move || {
+        rwlock_thread
+            .try_write()
+            .map(|mut guard| *guard = 5)
+            .is_ok()
+    })
+    .join()
+    .map_err(|_| "Thread panicked".to_string())?;
+    assert_res!(written);
+    assert_eq_res!(
+        *rwlock
+            .try_read()
+            .map_err(|_| "Failed to lock RwLock for reading".to_string())?,
+        5
+    );
+    Ok(())
+}
+
+// Test that readers hold an RwLock at the same time
+pub fn test_rwlock_concurrent_readers() -> Result<(), Box<dyn std::error::Error>> {
+    let rwlock = Arc::new(RwLock::new(0));
+    // A barrier with a timeout, so serialized readers fail instead of hanging
+    let inside = Arc::new((Mutex::new(0), Condvar::new()));
+    let mut handles = vec![];
+
+    for _ in 0..THREAD_COUNT {
+        let rwlock = Arc::clone(&rwlock);
+        let inside = Arc::clone(&inside);
+        let handle = thread::spawn(move || {
+            let _guard = rwlock
+                .read()
+                .map_err(|_| "Failed to lock RwLock for reading".to_string())?;
+            let (lock, cvar) = &*inside;
+            let mut count = lock
+                .lock()
+                .map_err(|_| "Failed to lock mutex".to_string())?;
+            *count += 1;
+            cvar.notify_all();
+            let (count, result) = cvar
+                .wait_timeout_while(count, Duration::from_secs(5), |count| *count < THREAD_COUNT)
+                .map_err(|_| "Failed to wait".to_string())?;
+            if result.timed_out() {
+                return Err(format!(
+                    "Only {} of {THREAD_COUNT} readers held the lock at once",
+                    *count
+                ));
+            }
+            Ok(())
+        });
+        handles.push(handle);
+    }
+
+    for handle in handles {
+        handle.join().map_err(|_| "Thread panicked".to_string())??;
+    }
+    Ok(())
+}
+
+// Test that a writer gets the lock while readers keep acquiring it
+pub fn test_rwlock_writer_not_starved() -> Result<(), Box<dyn std::error::Error>> {
+    let rwlock = Arc::new(RwLock::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    let barrier = Arc::new(Barrier::new(THREAD_COUNT + 1));
+    let mut readers = vec![];
+
+    for _ in 0..THREAD_COUNT {
+        let rwlock = Arc::clone(&rwlock);
+        let stop = Arc::clone(&stop);
+        let barrier = Arc::clone(&barrier);
+        readers.push(thread::spawn(move || {
+            barrier.wait();
+            let deadline = std::time::Instant::now() + Duration::from_secs(5);
+            // Overlapping read guards, so the lock is always held by some reader
+            while !stop.load(Ordering::Acquire) && std::time::Instant::now() < deadline {
+                let _guard = rwlock
+                    .read()
+                    .map_err(|_| "Failed to lock RwLock for reading".to_string())?;
+                thread::sleep(Duration::from_millis(1));
+            }
+            Ok::<_, String>(())
+        }));
+    }
+
+    barrier.wait();
+    thread::sleep(Duration::from_millis(20));
+    let start = std::time::Instant::now();
+    *rwlock
+        .write()
+        .map_err(|_| "Failed to lock RwLock for writing".to_string())? += 1;
+    let waited = start.elapsed();
+    stop.store(true, Ordering::Release);
+
+    for handle in readers {
+        handle.join().map_err(|_| "Thread panicked".to_string())??;
+    }
+    if waited >= Duration::from_secs(2) {
+        return Err(format!(
+            "Writer waited {waited:?} for readers to let it in\n{}",
+            "This test is timing-based, try re-running it on an idle system"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+// Test downgrading a write guard to a read guard without letting other writers in
+pub fn test_rwlock_downgrade() -> Result<(), Box<dyn std::error::Error>> {
+    use std::sync::RwLockWriteGuard;
+
+    let rwlock = Arc::new(RwLock::new(0));
+    let mut write_guard = rwlock
+        .write()
+        .map_err(|_| "Failed to lock RwLock for writing".to_string())?;
+    *write_guard = 1;
+    let read_guard = RwLockWriteGuard::downgrade(write_guard);
+    assert_eq_res!(*read_guard, 1);
+
+    let rwlock_thread = Arc::clone(&rwlock);
+    let (read_value, write_blocked) = thread::spawn(move || {
+        let read_value = rwlock_thread.try_read().map(|guard| *guard).ok();
+        let write_blocked = matches!(rwlock_thread.try_write(), Err(TryLockError::WouldBlock));
+        (read_value, write_blocked)
+    })
+    .join()
+    .map_err(|_| "Thread panicked".to_string())?;
+    assert_eq_res!(read_value, Some(1));
+    assert_res!(write_blocked);
+
+    // A writer waiting during the downgrade sees the value written before it
+    let rwlock_thread = Arc::clone(&rwlock);
+    let writer = thread::spawn(move || {
+        let mut guard = rwlock_thread
+            .write()
+            .map_err(|_| "Failed to lock RwLock for writing".to_string())?;
+        let seen = *guard;
+        *guard = 2;
+        Ok::<_, String>(seen)
+    });
+    thread::sleep(Duration::from_millis(20));
+    assert_eq_res!(*read_guard, 1);
+    drop(read_guard);
+
+    let seen = writer.join().map_err(|_| "Thread panicked".to_string())??;
+    assert_eq_res!(seen, 1);
+    let value = *rwlock
+        .read()
+        .map_err(|_| "Failed to lock RwLock for reading".to_string())?;
+    assert_eq_res!(value, 2);
+    Ok(())
+}
+
+// Test atomic read-modify-write operations from many threads
+pub fn test_atomic_counters() -> Result<(), Box<dyn std::error::Error>> {
+    const INCREMENTS: usize = 10_000;
+    let counter = Arc::new(AtomicUsize::new(0));
+    let maximum = Arc::new(AtomicUsize::new(0));
+    let flags = Arc::new(AtomicUsize::new(0));
+    let mut handles = vec![];
+
+    for i in 0..THREAD_COUNT {
+        let counter = Arc::clone(&counter);
+        let maximum = Arc::clone(&maximum);
+        let flags = Arc::clone(&flags);
+        handles.push(thread::spawn(move || {
+            for j in 0..INCREMENTS {
+                counter.fetch_add(1, Ordering::Relaxed);
+                maximum.fetch_max(i * INCREMENTS + j, Ordering::Relaxed);
+            }
+            flags.fetch_or(1 << i, Ordering::Relaxed);
+            counter.fetch_sub(1, Ordering::Relaxed);
+        }));
+    }
+
+    for handle in handles {
+        handle.join().map_err(|_| "Thread panicked".to_string())?;
+    }
+    assert_eq_res!(
+        counter.load(Ordering::Relaxed),
+        THREAD_COUNT * INCREMENTS - THREAD_COUNT
+    );
+    assert_eq_res!(
+        maximum.load(Ordering::Relaxed),
+        THREAD_COUNT * INCREMENTS - 1
+    );
+    assert_eq_res!(flags.load(Ordering::Relaxed), (1 << THREAD_COUNT) - 1);
+    Ok(())
+}
+
+// Test compare_exchange loops, fetch_update and AtomicBool from many threads
+pub fn test_atomic_compare_exchange() -> Result<(), Box<dyn std::error::Error>> {
+    const INCREMENTS: usize = 10_000;
+    let counter = Arc::new(AtomicUsize::new(0));
+    let updated = Arc::new(AtomicUsize::new(0));
+    let claimed = Arc::new(AtomicBool::new(false));
+    let barrier = Arc::new(Barrier::new(THREAD_COUNT));
+    let mut handles = vec![];
+
+    for _ in 0..THREAD_COUNT {
+        let counter = Arc::clone(&counter);
+        let updated = Arc::clone(&updated);
+        let claimed = Arc::clone(&claimed);
+        let barrier = Arc::clone(&barrier);
+        handles.push(thread::spawn(move || {
+            barrier.wait();
+            // Only a single thread may win the claim
+            let won = claimed
+                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok();
+            for _ in 0..INCREMENTS {
+                let mut current = counter.load(Ordering::Relaxed);
+                loop {
+                    match counter.compare_exchange_weak(
+                        current,
+                        current + 1,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    ) {
+                        Ok(_) => break,
+                        Err(actual) => current = actual,
+                    }
+                }
+                updated
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |value| {
+                        Some(value + 2)
+                    })
+                    .map_err(|_| "fetch_update failed".to_string())?;
+            }
+            Ok::<_, String>(won)
+        }));
+    }
+
+    let mut winners = 0;
+    for handle in handles {
+        if handle.join().map_err(|_| "Thread panicked".to_string())?? {
+            winners += 1;
+        }
+    }
+    assert_eq_res!(winners, 1);
+    assert_res!(claimed.load(Ordering::Acquire));
+    assert_eq_res!(counter.load(Ordering::Relaxed), THREAD_COUNT * INCREMENTS);
+    assert_eq_res!(
+        updated.load(Ordering::Relaxed),
+        2 * THREAD_COUNT * INCREMENTS
+    );
+
+    // A failing update leaves the value untouched
+    let result = updated.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |_| None);
+    assert_eq_res!(result, Err(2 * THREAD_COUNT * INCREMENTS));
+    Ok(())
+}
+
+// Test publishing heap values to other threads through AtomicPtr
+pub fn test_atomic_ptr() -> Result<(), Box<dyn std::error::Error>> {
+    use std::sync::atomic::AtomicPtr;
+
+    let slot = Arc::new(AtomicPtr::new(std::ptr::null_mut::<[usize; 16]>()));
+    let stop = Arc::new(AtomicBool::new(false));
+    let (slot_producer, stop_producer) = (Arc::clone(&slot), Arc::clone(&stop));
+    let producer = thread::spawn(move || {
+        for round in 1..=ITER_COUNT * 50 {
+            let value = Box::into_raw(Box::new([round; 16]));
+            // Wait until the consumer took the previous value
+            while slot_producer
+                .compare_exchange(
+                    std::ptr::null_mut(),
+                    value,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                )
+                .is_err()
+            {
+                if stop_producer.load(Ordering::Relaxed) {
+                    // SAFETY: the value was never published, so we still own it
+                    drop(unsafe { Box::from_raw(value) });
+                    return;
+                }
+                thread::yield_now();
+            }
+        }
+    });
+
+    let consume = || -> Result<(), Box<dyn std::error::Error>> {
+        let mut previous = 0;
+        while previous < ITER_COUNT * 50 {
+            let value = slot.swap(std::ptr::null_mut(), Ordering::Acquire);
+            if value.is_null() {
+                thread::yield_now();
+                continue;
+            }
+            // SAFETY: the pointer came from Box::into_raw and the swap made us its only owner
+            let value = unsafe { Box::from_raw(value) };
+            // Acquire makes the whole array written by the producer visible
+            assert_res!(value.iter().all(|&v| v == previous + 1));
+            previous += 1;
+        }
+        Ok(())
+    };
+    let result = consume();
+
+    // Stop the producer even if the consumer failed, so it does not spin for the rest of the run
+    stop.store(true, Ordering::Relaxed);
+    producer.join().map_err(|_| "Thread panicked".to_string())?;
+    let left = slot.swap(std::ptr::null_mut(), Ordering::Acquire);
+    if !left.is_null() {
+        // SAFETY: the producer has exited, so the published value is only ours
+        drop(unsafe { Box::from_raw(left) });
+    }
+    result?;
+    assert_res!(left.is_null());
+    Ok(())
+}
+
+// Message passing litmus test: data written before a Release store is seen after an Acquire load
+pub fn test_atomic_message_passing() -> Result<(), Box<dyn std::error::Error>> {
+    const ROUNDS: usize = 10_000;
+    let data = Arc::new([const { AtomicUsize::new(0) }; 4]);
+    let ready = Arc::new(AtomicUsize::new(0));
+    let acknowledged = Arc::new(AtomicUsize::new(0));
+
+    let (data_writer, ready_writer, ack_writer) = (
+        Arc::clone(&data),
+        Arc::clone(&ready),
+        Arc::clone(&acknowledged),
+    );
+    let writer = thread::spawn(move || {
+        for round in 1..=ROUNDS {
+            for (i, cell) in data_writer.iter().enumerate() {
+                cell.store(round * 10 + i, Ordering::Relaxed);
+            }
+            ready_writer.store(round, Ordering::Release);
+            while ack_writer.load(Ordering::Acquire) != round {
+                std::hint::spin_loop();
+                thread::yield_now();
+            }
+        }
+    });
+
+    let mut failures = 0;
+    for round in 1..=ROUNDS {
+        while ready.load(Ordering::Acquire) != round {
+            std::hint::spin_loop();
+            thread::yield_now();
+        }
+        for (i, cell) in data.iter().enumerate() {
+            if cell.load(Ordering::Relaxed) != round * 10 + i {
+                failures += 1;
+            }
+        }
+        acknowledged.store(round, Ordering::Release);
+    }
+
+    writer.join().map_err(|_| "Thread panicked".to_string())?;
+    assert_eq_res!(failures, 0);
+    Ok(())
+}
+
+// Test 64-bit atomics, which may be emulated on 32-bit targets
+#[cfg(target_has_atomic = "64")]
+pub fn test_atomic_u64() -> Result<(), Box<dyn std::error::Error>> {
+    use std::sync::atomic::AtomicU64;
+
+    const INCREMENTS: u64 = 10_000;
+    // Start right below 2^32, so the counter crosses into the upper half during the test
+    let start = u64::from(u32::MAX) - 1000;
+    let counter = Arc::new(AtomicU64::new(start));
+    let wide = Arc::new(AtomicU64::new(0));
+    let mut handles = vec![];
+
+    for i in 0..THREAD_COUNT as u64 {
+        let counter = Arc::clone(&counter);
+        let wide = Arc::clone(&wide);
+        handles.push(thread::spawn(move || {
+            for _ in 0..INCREMENTS {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+            // Both halves of the value must be written at once
+            let pattern = (i << 32) | i;
+            for _ in 0..INCREMENTS {
+                let value = wide.swap(pattern, Ordering::AcqRel);
+                if value >> 32 != value & 0xffff_ffff {
+                    return Err(format!("Torn 64-bit value {value:#x}"));
+                }
+            }
+            Ok::<_, String>(())
+        }));
+    }
+
+    for handle in handles {
+        handle.join().map_err(|_| "Thread panicked".to_string())??;
+    }
+    assert_eq_res!(
+        counter.load(Ordering::Relaxed),
+        start + THREAD_COUNT as u64 * INCREMENTS
+    );
+
+    let value = AtomicU64::new(u64::MAX);
+    assert_eq_res!(value.fetch_add(1, Ordering::Relaxed), u64::MAX);
+    assert_eq_res!(value.load(Ordering::Relaxed), 0);
+    assert_eq_res!(
+        value.compare_exchange(0, 1 << 40, Ordering::AcqRel, Ordering::Acquire),
+        Ok(0)
+    );
+    assert_eq_res!(value.load(Ordering::Relaxed), 1 << 40);
+    Ok(())
+}
+
+#[cfg(not(target_has_atomic = "64"))]
+pub fn test_atomic_u64() -> Result<(), Box<dyn std::error::Error>> {
+    Err(crate::Skipped("64-bit atomics are not available on this target".into()).into())
+}
+
+// Test that a zero-capacity sync_channel hands messages over directly
+pub fn test_sync_channel_rendezvous() -> Result<(), Box<dyn std::error::Error>> {
+    let (sender, receiver) = mpsc::sync_channel(0);
+    // Nobody is receiving, so there is nowhere to put the message
+    assert_res!(matches!(
+        sender.try_send(1),
+        Err(mpsc::TrySendError::Full(1))
+    ));
+
+    let delay = Duration::from_millis(50);
+    // Measured from before the spawn, so a late starting thread cannot shorten the wait
+    let start = std::time::Instant::now();
+    let sender_handle = thread::spawn(move || {
+        sender
+            .send(2)
+            .map_err(|_| "Failed to send message".to_string())?;
+        Ok::<_, String>(start.elapsed())
+    });
+
+    thread::sleep(delay);
+    let received = receiver
+        .recv()
+        .map_err(|_| "Failed to receive message".to_string())?;
+    assert_eq_res!(received, 2);
+    let blocked = sender_handle
+        .join()
+        .map_err(|_| "Sender thread panicked".to_string())??;
+    // The send only completes once the receiver takes the message, after the sleep
+    assert_res!(blocked >= delay);
+    Ok(())
+}
+
+// Test that a bounded sync_channel limits how far the sender gets ahead
+pub fn test_sync_channel_bounded() -> Result<(), Box<dyn std::error::Error>> {
+    const CAPACITY: usize = 3;
+    let (sender, receiver) = mpsc::sync_channel(CAPACITY);
+    for i in 0..CAPACITY {
+        sender.try_send(i)?;
+    }
+    assert_res!(matches!(
+        sender.try_send(CAPACITY),
+        Err(mpsc::TrySendError::Full(_))
+    ));
+    assert_eq_res!(receiver.recv()?, 0);
+    sender.try_send(CAPACITY)?;
+    for i in 1..=CAPACITY {
+        assert_eq_res!(receiver.recv()?, i);
+    }
+
+    let sent = Arc::new(AtomicUsize::new(0));
+    let sent_thread = Arc::clone(&sent);
+    let sender_handle = thread::spawn(move || {
+        for i in 0..ITER_COUNT * 5 {
+            sender
+                .send(i)
+                .map_err(|_| "Failed to send message".to_string())?;
+            sent_thread.fetch_add(1, Ordering::SeqCst);
+        }
+        Ok::<_, String>(())
+    });
+
+    for i in 0..ITER_COUNT * 5 {
+        if i % 10 == 0 {
+            // Give the sender time to fill the channel
+            thread::sleep(Duration::from_millis(2));
+        }
+        let in_flight = sent.load(Ordering::SeqCst).saturating_sub(i);
+        // At most a full buffer plus a message being handed over
+        assert_res!(in_flight <= CAPACITY + 1);
+        assert_eq_res!(receiver.recv()?, i);
+    }
+    sender_handle
+        .join()
+        .map_err(|_| "Sender thread panicked".to_string())??;
+    Ok(())
+}
+
+// Test many cloned senders, with the receiver iterator ending once all are dropped
+pub fn test_channel_multiple_senders() -> Result<(), Box<dyn std::error::Error>> {
+    let (sender, receiver) = mpsc::channel();
+    let mut handles = vec![];
+
+    for t in 0..THREAD_COUNT {
+        let sender = sender.clone();
+        handles.push(thread::spawn(move || {
+            for i in 0..ITER_COUNT {
+                sender
+                    .send((t, i))
+                    .map_err(|_| "Failed to send message".to_string())?;
+            }
+            Ok::<_, String>(())
+        }));
+    }
+    drop(sender);
+
+    let mut next = [0; THREAD_COUNT];
+    for (t, i) in receiver.iter() {
+        // Messages from one sender arrive in order
+        assert_eq_res!(i, next[t]);
+        next[t] += 1;
+    }
+    assert_eq_res!(next, [ITER_COUNT; THREAD_COUNT]);
+
+    for handle in handles {
+        handle.join().map_err(|_| "Thread panicked".to_string())??;
+    }
+    Ok(())
+}
+
+// Test try_recv and recv_timeout on an empty and a disconnected channel
+pub fn test_channel_try_recv() -> Result<(), Box<dyn std::error::Error>> {
+    let (sender, receiver) = mpsc::channel();
+    assert_eq_res!(receiver.try_recv(), Err(mpsc::TryRecvError::Empty));
+    let start = std::time::Instant::now();
+    assert_eq_res!(
+        receiver.recv_timeout(Duration::from_millis(20)),
+        Err(mpsc::RecvTimeoutError::Timeout)
+    );
+    assert_res!(start.elapsed() >= Duration::from_millis(20));
+
+    sender.send(1)?;
+    sender.send(2)?;
+    assert_eq_res!(receiver.try_recv(), Ok(1));
+    drop(sender);
+    // Messages sent before the disconnect are still delivered
+    assert_eq_res!(receiver.recv_timeout(Duration::from_secs(1)), Ok(2));
+    assert_eq_res!(receiver.try_recv(), Err(mpsc::TryRecvError::Disconnected));
+    assert_eq_res!(
+        receiver.recv_timeout(Duration::from_secs(1)),
+        Err(mpsc::RecvTimeoutError::Disconnected)
+    );
+    assert_res!(receiver.recv().is_err());
+
+    // A sender dropped by another thread wakes up a blocked receiver
+    let (sender, receiver) = mpsc::channel::<i32>();
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        drop(sender);
+    });
+    assert_eq_res!(
+        receiver.recv_timeout(Duration::from_secs(5)),
+        Err(mpsc::RecvTimeoutError::Disconnected)
+    );
+    handle.join().map_err(|_| "Thread panicked".to_string())?;
+    Ok(())
+}
+
+// Test that sending fails once the receiver is dropped
+pub fn test_channel_send_after_receiver_drop() -> Result<(), Box<dyn std::error::Error>> {
+    let (sender, receiver) = mpsc::channel();
+    sender.send(1)?;
+    drop(receiver);
+    match sender.send(2) {
+        // The unsent value is given back
+        Err(mpsc::SendError(value)) => assert_eq_res!(value, 2),
+        Ok(()) => return Err("Send succeeded without a receiver".into()),
+    }
+
+    let (sender, receiver) = mpsc::sync_channel(1);
+    let handle = thread::spawn(move || {
+        sender
+            .send(1)
+            .map_err(|_| "Failed to send message".to_string())?;
+        // This one blocks on the full buffer until the receiver goes away
+        Ok::<_, String>(sender.send(2).is_err())
+    });
+    thread::sleep(Duration::from_millis(20));
+    drop(receiver);
+    let failed = handle
+        .join()
+        .map_err(|_| "Sender thread panicked".to_string())??;
+    assert_res!(failed);
+    Ok(())
+}
+
+// Test sending many messages from several threads
+pub fn test_channel_throughput() -> Result<(), Box<dyn std::error::Error>> {
+    let per_sender = 100_000 * crate::scale() as usize;
+    let sender_count = 4;
+    let (sender, receiver) = mpsc::channel();
+    let (sync_sender, sync_receiver) = mpsc::sync_channel(64);
+    let mut handles = vec![];
+
+    for t in 0..sender_count {
+        let sender = sender.clone();
+        let sync_sender = sync_sender.clone();
+        handles.push(thread::spawn(move || {
+            for i in 0..per_sender {
+                sender
+                    .send(t * per_sender + i)
+                    .map_err(|_| "Failed to send message".to_string())?;
+                sync_sender
+                    .send(t * per_sender + i)
+                    .map_err(|_| "Failed to send message".to_string())?;
+            }
+            Ok::<_, String>(())
+        }));
+    }
+    drop(sender);
+    drop(sync_sender);
+
+    let total = sender_count * per_sender;
+    let sync_handle = thread::spawn(move || {
+        sync_receiver.iter().fold((0, 0), |(count, sum), value| {
+            (count + 1, sum + value as u64)
+        })
+    });
+    let (count, sum) = receiver.iter().fold((0, 0), |(count, sum), value| {
+        (count + 1, sum + value as u64)
+    });
+    let (sync_count, sync_sum) = sync_handle
+        .join()
+        .map_err(|_| "Receiver thread panicked".to_string())?;
+
+    for handle in handles {
+        handle.join().map_err(|_| "Thread panicked".to_string())??;
+    }
+    // The sum does not fit in a 32-bit usize
+    let expected_sum = total as u64 * (total as u64 - 1) / 2;
+    assert_eq_res!((count, sum), (total, expected_sum));
+    assert_eq_res!((sync_count, sync_sum), (total, expected_sum));
+    Ok(())
+}
+
+// Test scoped threads mutably borrowing disjoint chunks of a local vector
+pub fn test_scoped_parallel_sum() -> Result<(), Box<dyn std::error::Error>> {
+    let mut numbers: Vec<u64> = (0..100_000).collect();
+    let chunk_size = numbers.len().div_ceil(THREAD_COUNT);
+
+    let partial_sums = thread::scope(|s| {
+        let handles: Vec<_> = numbers
+            .chunks_mut(chunk_size)
+            .map(|chunk| {
+                s.spawn(move || {
+                    for number in chunk.iter_mut() {
+                        *number *= 2;
+                    }
+                    chunk.iter().sum::<u64>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().map_err(|_| "Thread panicked".to_string()))
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+
+    assert_eq_res!(partial_sums.len(), THREAD_COUNT);
+    let n = numbers.len() as u64;
+    assert_eq_res!(partial_sums.iter().sum::<u64>(), n * (n - 1));
+    // Changes made by the threads are visible after the scope
+    assert_res!(numbers.iter().enumerate().all(|(i, &v)| v == 2 * i as u64));
+    Ok(())
+}
+
+// Test that thread::scope waits for all threads that were not joined
+pub fn test_scope_joins_automatically() -> Result<(), Box<dyn std::error::Error>> {
+    let finished = AtomicUsize::new(0);
+    let result = thread::scope(|s| {
+        for i in 0..THREAD_COUNT {
+            let finished = &finished;
+            s.spawn(move || {
+                thread::sleep(Duration::from_millis(5 * i as u64));
+                finished.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        "scope result"
+    });
+    assert_eq_res!(result, "scope result");
+    assert_eq_res!(finished.load(Ordering::SeqCst), THREAD_COUNT);
+    Ok(())
+}
+
+// Test that a panic in a scoped thread propagates out of the scope unless it was joined
+pub fn test_scoped_thread_panic() -> Result<(), Box<dyn std::error::Error>> {
+    let finished = AtomicBool::new(false);
+    let result = silence_panics(|| {
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            thread::scope(|s| {
+                s.spawn(|| panic!("Scoped thread panicked"));
+                s.spawn(|| {
+                    thread::sleep(Duration::from_millis(10));
+                    finished.store(true, Ordering::SeqCst);
+                });
+            })
+        }))
+    });
+    assert_res!(result.is_err());
+    // The scope still waited for the other thread before propagating the panic
+    assert_res!(finished.load(Ordering::SeqCst));
+
+    let joined =
+        silence_panics(|| thread::scope(|s| s.spawn(|| panic!("Scoped thread panicked")).join()));
+    assert_res!(joined.is_err());
+    Ok(())
+}
+
+// Test nested scopes and scoped threads spawning more threads in the same scope
+pub fn test_nested_scopes() -> Result<(), Box<dyn std::error::Error>> {
+    let data: Vec<usize> = (0..THREAD_COUNT).collect();
+    let total = AtomicUsize::new(0);
+
+    thread::scope(|outer| {
+        for &value in &data {
+            let total = &total;
+            let data = &data;
+            outer.spawn(move || {
+                thread::scope(|inner| {
+                    for &other in data {
+                        inner.spawn(move || total.fetch_add(value * other, Ordering::SeqCst));
+                    }
+                });
+            });
+        }
+    });
+    let sum: usize = data.iter().sum();
+    assert_eq_res!(total.load(Ordering::SeqCst), sum * sum);
+
+    let spawned = AtomicUsize::new(0);
+    thread::scope(|s| {
+        let spawned = &spawned;
+        for _ in 0..THREAD_COUNT {
+            s.spawn(move || {
+                spawned.fetch_add(1, Ordering::SeqCst);
+                // The scope can be used from its own threads
+                s.spawn(move || spawned.fetch_add(1, Ordering::SeqCst));
+            });
+        }
+    });
+    assert_eq_res!(spawned.load(Ordering::SeqCst), 2 * THREAD_COUNT);
+    Ok(())
+}
+
+// Test many threads racing to initialize a OnceLock, only one initializer may run
+pub fn test_oncelock_race() -> Result<(), Box<dyn std::error::Error>> {
+    let lock = OnceLock::new();
+    let init_count = AtomicUsize::new(0);
+    let barrier = Barrier::new(THREAD_COUNT);
+
+    let values = thread::scope(|s| {
+        let handles: Vec<_> = (0..THREAD_COUNT)
+            .map(|i| {
+                let (lock, init_count, barrier) = (&lock, &init_count, &barrier);
+                s.spawn(move || {
+                    barrier.wait();
+                    *lock.get_or_init(|| {
+                        init_count.fetch_add(1, Ordering::SeqCst);
+                        // Keep the others waiting for the initialization for a while
+                        thread::sleep(Duration::from_millis(20));
+                        i
+                    })
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().map_err(|_| "Thread panicked".to_string()))
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+
+    assert_eq_res!(init_count.load(Ordering::SeqCst), 1);
+    let winner = *lock.get().ok_or("OnceLock is not initialized")?;
+    assert_res!(values.iter().all(|&value| value == winner));
+    Ok(())
+}
+
+// Test that OnceLock::set only succeeds once
+pub fn test_oncelock_set() -> Result<(), Box<dyn std::error::Error>> {
+    let lock = OnceLock::new();
+    assert_eq_res!(lock.get(), None);
+    assert_eq_res!(lock.set(1), Ok(()));
+    assert_eq_res!(lock.set(2), Err(2));
+    assert_eq_res!(lock.get_or_init(|| 3), &1);
+    assert_eq_res!(lock.get(), Some(&1));
+
+    let lock = OnceLock::new();
+    let barrier = Barrier::new(THREAD_COUNT);
+    let successes = thread::scope(|s| {
+        let handles: Vec<_> = (0..THREAD_COUNT)
+            .map(|i| {
+                let (lock, barrier) = (&lock, &barrier);
+                s.spawn(move || {
+                    barrier.wait();
+                    lock.set(i).is_ok()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().map_err(|_| "Thread panicked".to_string()))
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+    assert_eq_res!(successes.iter().filter(|&&success| success).count(), 1);
+    let winner = successes.iter().position(|&success| success);
+    assert_eq_res!(lock.get().copied(), winner);
+    Ok(())
+}
+
+// Test Once with concurrent callers and with a panicking initializer
+pub fn test_once_poisoning() -> Result<(), Box<dyn std::error::Error>> {
+    use std::sync::Once;
+
+    let once = Once::new();
+    let init_count = AtomicUsize::new(0);
+    thread::scope(|s| {
+        let handles: Vec<_> = (0..THREAD_COUNT)
+            .map(|_| {
+                s.spawn(|| {
+                    once.call_once(|| {
+                        thread::sleep(Duration::from_millis(10));
+                        init_count.fetch_add(1, Ordering::SeqCst);
+                    });
+                    init_count.load(Ordering::SeqCst)
+                })
+            })
+            .collect();
+        for handle in handles {
+            let seen = handle.join().map_err(|_| "Thread panicked".to_string())?;
+            // call_once only returns after the initialization finished
+            assert_eq_res!(seen, 1);
+        }
+        Ok::<_, String>(())
+    })?;
+    assert_eq_res!(init_count.load(Ordering::SeqCst), 1);
+    assert_res!(once.is_completed());
+
+    let once = Once::new();
+    let result = silence_panics(|| {
+        std::panic::catch_unwind(|| once.call_once(|| panic!("Initializer panicked")))
+    });
+    assert_res!(result.is_err());
+    assert_res!(!once.is_completed());
+
+    // A poisoned Once panics for further call_once calls
+    let result = silence_panics(|| std::panic::catch_unwind(|| once.call_once(|| {})));
+    assert_res!(result.is_err());
+    assert_res!(!once.is_completed());
+
+    let mut was_poisoned = false;
+    once.call_once_force(|state| was_poisoned = state.is_poisoned());
+    assert_res!(was_poisoned);
+    assert_res!(once.is_completed());
+    Ok(())
+}
+
+// Test LazyLock statics and locals accessed from many threads at once
+pub fn test_lazylock() -> Result<(), Box<dyn std::error::Error>> {
+    use std::collections::HashMap;
+    use std::sync::LazyLock;
+
+    static INIT_COUNT: AtomicUsize = AtomicUsize::new(0);
+    static TABLE: LazyLock<HashMap<usize, String>> = LazyLock::new(|| {
+        INIT_COUNT.fetch_add(1, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(20));
+        (0..THREAD_COUNT)
+            .map(|i| (i, format!("value {i}")))
+            .collect()
+    });
+
+    let barrier = Barrier::new(THREAD_COUNT);
+    thread::scope(|s| {
+        let handles: Vec<_> = (0..THREAD_COUNT)
+            .map(|i| {
+                let barrier = &barrier;
+                s.spawn(move || {
+                    barrier.wait();
+                    TABLE.get(&i).cloned()
+                })
+            })
+            .collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            let value = handle.join().map_err(|_| "Thread panicked".to_string())?;
+            assert_eq_res!(value, Some(format!("value {i}")));
+        }
+        Ok::<_, String>(())
+    })?;
+    assert_eq_res!(INIT_COUNT.load(Ordering::SeqCst), 1);
+    assert_eq_res!(TABLE.len(), THREAD_COUNT);
+
+    let base = 40;
+    let local = LazyLock::new(|| base + 2);
+    let value =
+        thread::scope(|s| s.spawn(|| *local).join()).map_err(|_| "Thread panicked".to_string())?;
+    assert_eq_res!(value, 42);
+    assert_eq_res!(LazyLock::force(&local), &42);
+    Ok(())
+}
+
+// Which of the thread-locals below were accessible when a reporter was dropped
+type TlsAccess = [bool; 3];
+
+// Reports through a channel when a thread-local holding it is destroyed
+struct TlsDropReporter {
+    name: &'static str,
+    sender: mpsc::Sender<(&'static str, TlsAccess)>,
+}
+
+impl Drop for TlsDropReporter {
+    fn drop(&mut self) {
+        let access = [
+            TLS_LAZY.try_with(|_| ()).is_ok(),
+            TLS_CONST.try_with(|_| ()).is_ok(),
+            TLS_CONST_OTHER.try_with(|_| ()).is_ok(),
+        ];
+        let _ = self.sender.send((self.name, access));
+    }
+}
+
+type TlsSlot = std::cell::RefCell<Option<TlsDropReporter>>;
+
+thread_local! {
+    // Lazily initialized on first access
+    static TLS_LAZY: std::cell::RefCell<Vec<TlsDropReporter>> =
+        std::cell::RefCell::new(Vec::with_capacity(1));
+    static TLS_CONST: TlsSlot = const { TlsSlot::new(None) };
+    static TLS_CONST_OTHER: TlsSlot = const { TlsSlot::new(None) };
+}
+
+// Initializes the thread-locals of the current thread in the given order
+fn init_tls_reporters(names: &[&'static str], sender: &mpsc::Sender<(&'static str, TlsAccess)>) {
+    for &name in names {
+        let reporter = TlsDropReporter {
+            name,
+            sender: sender.clone(),
+        };
+        match name {
+            "lazy" => TLS_LAZY.with(|tls| tls.borrow_mut().push(reporter)),
+            "const" => TLS_CONST.with(|tls| *tls.borrow_mut() = Some(reporter)),
+            _ => TLS_CONST_OTHER.with(|tls| *tls.borrow_mut() = Some(reporter)),
+        }
+    }
+}
+
+fn receive_tls_reports(
+    receiver: &mpsc::Receiver<(&'static str, TlsAccess)>,
+    count: usize,
+) -> Result<Vec<(&'static str, TlsAccess)>, String> {
+    (0..count)
+        .map(|i| {
+            receiver
+                .recv_timeout(Duration::from_secs(5))
+                .map_err(|_| format!("Destructor {} of {count} did not run", i + 1))
+        })
+        .collect()
+}
+
+// Test that thread-local destructors run when a thread exits
+pub fn test_thread_local_destructors() -> Result<(), Box<dyn std::error::Error>> {
+    let (sender, receiver) = mpsc::channel();
+    let mut handles = vec![];
+
+    for i in 0..THREAD_COUNT {
+        let sender = sender.clone();
+        handles.push(thread::spawn(move || {
+            // Half of the threads only use the lazy key, the rest only a const one
+            let names: &[&str] = if i % 2 == 0 { &["lazy"] } else { &["const"] };
+            init_tls_reporters(names, &sender);
+        }));
+    }
+    drop(sender);
+
+    for handle in handles {
+        handle.join().map_err(|_| "Thread panicked".to_string())?;
+    }
+    let reports = receive_tls_reports(&receiver, THREAD_COUNT)?;
+    let lazy_count = reports.iter().filter(|(name, _)| *name == "lazy").count();
+    assert_eq_res!(lazy_count, THREAD_COUNT / 2);
+    // No destructor ran twice, and nothing is left once all senders are gone
+    assert_res!(receiver.recv_timeout(Duration::from_secs(1)).is_err());
+
+    // The main thread's own values are not affected by the other threads exiting
+    assert_res!(TLS_LAZY.with(|tls| tls.borrow().is_empty()));
+    Ok(())
+}
+
+// Test that destructors of multiple thread-locals run in reverse order of initialization
+pub fn test_thread_local_destructor_order() -> Result<(), Box<dyn std::error::Error>> {
+    for order in [
+        ["lazy", "const", "const_other"],
+        ["const_other", "lazy", "const"],
+    ] {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || init_tls_reporters(&order, &sender))
+            .join()
+            .map_err(|_| "Thread panicked".to_string())?;
+
+        let reports = receive_tls_reports(&receiver, order.len())?;
+        let dropped: Vec<_> = reports.iter().map(|(name, _)| *name).collect();
+        // std runs them like C++ thread_local destructors, last initialized first
+        let mut expected = order.to_vec();
+        expected.reverse();
+        assert_eq_res!(dropped, expected);
+    }
+    Ok(())
+}
+
+// Test try_with on thread-locals from within another thread-local's destructor
+pub fn test_thread_local_access_in_destructor() -> Result<(), Box<dyn std::error::Error>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || init_tls_reporters(&["lazy", "const", "const_other"], &sender))
+        .join()
+        .map_err(|_| "Thread panicked".to_string())?;
+
+    let reports = receive_tls_reports(&receiver, 3)?;
+    for (name, access) in reports {
+        // A key being destroyed, and any key destroyed before it, gives AccessError
+        let expected = match name {
+            "const_other" => [true, true, false],
+            "const" => [true, false, false],
+            _ => [false, false, false],
+        };
+        if access != expected {
+            return Err(format!(
+                "In destructor of {name}: accessible [lazy, const, const_other] = {access:?}, expected {expected:?}"
+            )
+            .into());
+        }
+    }
+
+    match TLS_CONST_OTHER.try_with(|tls| tls.borrow().is_none()) {
+        Ok(empty) => assert_res!(empty),
+        Err(e) => return Err(format!("Live thread-local not accessible: {e}").into()),
+    }
+    Ok(())
+}